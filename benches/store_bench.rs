@@ -1,7 +1,7 @@
 use bytemuck::{Pod, Zeroable};
-use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
 use roda_state::measure::LatencyMeasurer;
-use roda_state::{JournalStoreOptions, RodaEngine};
+use roda_state::{JournalStoreOptions, RodaEngine, StageEngine};
 use std::hint::black_box;
 
 #[derive(Clone, Copy, Zeroable, Pod)]
@@ -17,7 +17,7 @@ fn bench_push(c: &mut Criterion) {
     // 1GB buffer to ensure we don't overflow during benchmarking
     let size = 16 * 1024 * 1024 * 1024;
     let mut store_u64 = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "bench_push_u64",
+        name: "bench_push_u64".into(),
         size,
         in_memory: true,
     });
@@ -35,7 +35,7 @@ fn bench_push(c: &mut Criterion) {
     println!("push_u64 latency:{}", measurer.format_stats());
 
     let mut store_large = engine.new_journal_store::<LargeState>(JournalStoreOptions {
-        name: "bench_push_large",
+        name: "bench_push_large".into(),
         size,
         in_memory: true,
     });
@@ -59,7 +59,7 @@ fn bench_fetch(c: &mut Criterion) {
 
     let size = 1024 * 1024 * 100; // 100MB
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "bench_fetch",
+        name: "bench_fetch".into(),
         size,
         in_memory: true,
     });
@@ -90,7 +90,7 @@ fn bench_fetch(c: &mut Criterion) {
     println!("get_last_u64 latency:{}", measurer.format_stats());
 
     let mut store_large = engine.new_journal_store::<LargeState>(JournalStoreOptions {
-        name: "bench_fetch_large",
+        name: "bench_fetch_large".into(),
         size,
         in_memory: true,
     });
@@ -128,7 +128,7 @@ fn bench_window(c: &mut Criterion) {
 
     let size = 1024 * 1024 * 100; // 100MB
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "bench_window",
+        name: "bench_window".into(),
         size,
         in_memory: true,
     });
@@ -161,5 +161,43 @@ fn bench_window(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_push, bench_fetch, bench_window);
+fn bench_send_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("send");
+    let items: Vec<u64> = (0..10_000).collect();
+    group.throughput(Throughput::Elements(items.len() as u64));
+
+    group.bench_function("send_loop_10k", |b| {
+        b.iter_batched(
+            || StageEngine::<u64, u64>::with_capacity(items.len()),
+            |mut engine| {
+                for item in &items {
+                    engine.send(black_box(item));
+                }
+                engine
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.bench_function("send_batch_10k", |b| {
+        b.iter_batched(
+            || StageEngine::<u64, u64>::with_capacity(items.len()),
+            |mut engine| {
+                engine.send_batch(black_box(&items));
+                engine
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_push,
+    bench_fetch,
+    bench_window,
+    bench_send_batch
+);
 criterion_main!(benches);
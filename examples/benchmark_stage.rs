@@ -0,0 +1,92 @@
+use bytemuck::{Pod, Zeroable};
+use roda_state::testing::{BenchmarkResults, PipelineBenchmarkHarness};
+use roda_state::{OutputCollector, Stage};
+use std::hint::black_box;
+use std::time::Instant;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+struct Tick {
+    price: f64,
+    ewma: f64,
+}
+
+/// Exponentially weighted moving average over the tick price.
+struct Ewma {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Stage<Tick, Tick> for Ewma {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &Tick, collector: &mut C)
+    where
+        C: OutputCollector<Tick>,
+    {
+        let value = match self.value {
+            Some(prev) => prev + self.alpha * (data.price - prev),
+            None => data.price,
+        };
+        self.value = Some(value);
+        collector.push(&Tick {
+            price: data.price,
+            ewma: value,
+        });
+    }
+}
+
+fn print_results(name: &str, results: &BenchmarkResults) {
+    println!(
+        "[{}] items={}, outputs={}, elapsed={}us, throughput={:.0}/s, p50={}ns, p99={}ns",
+        name,
+        results.items,
+        results.outputs,
+        results.elapsed.as_micros(),
+        results.throughput,
+        results.latency.p50,
+        results.latency.p99,
+    );
+}
+
+fn main() {
+    let count = 1_000_000;
+    let inputs: Vec<Tick> = (0..count)
+        .map(|i| Tick {
+            price: 100.0 + (i % 100) as f64 * 0.01,
+            ewma: 0.0,
+        })
+        .collect();
+
+    // Baseline: the same computation as a plain loop
+    let start = Instant::now();
+    let mut value: Option<f64> = None;
+    for tick in &inputs {
+        let next = match value {
+            Some(prev) => prev + 0.1 * (tick.price - prev),
+            None => tick.price,
+        };
+        value = Some(black_box(next));
+    }
+    let baseline = start.elapsed();
+    println!(
+        "[baseline] items={}, elapsed={}us, throughput={:.0}/s",
+        count,
+        baseline.as_micros(),
+        count as f64 / baseline.as_secs_f64()
+    );
+
+    let results = PipelineBenchmarkHarness::run(
+        Ewma {
+            alpha: 0.1,
+            value: None,
+        },
+        &inputs,
+        10_000,
+    );
+    print_results("ewma", &results);
+
+    println!(
+        "Stage overhead vs baseline: {:.2}x",
+        results.elapsed.as_secs_f64() / baseline.as_secs_f64()
+    );
+}
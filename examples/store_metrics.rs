@@ -0,0 +1,184 @@
+//! Prints statistics of the `.store` files of file-backed journal stores.
+//!
+//! ```sh
+//! cargo run --example store_metrics -- data/*.store --type f64
+//! ```
+//!
+//! Store files do not record the write position, so the item count is the position of
+//! the last item that is not all zero bytes. Trailing zero items cannot be told apart
+//! from unused capacity.
+use clap::{Parser, ValueEnum};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Number of items shown per store.
+const PREVIEW_ITEMS: usize = 5;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ItemType {
+    U32,
+    U64,
+    F64,
+}
+
+impl ItemType {
+    fn size(self) -> usize {
+        match self {
+            ItemType::U32 => size_of::<u32>(),
+            ItemType::U64 => size_of::<u64>(),
+            ItemType::F64 => size_of::<f64>(),
+        }
+    }
+
+    fn format_all(self, bytes: &[u8]) -> Vec<String> {
+        match self {
+            ItemType::U32 => format_values(bytemuck::cast_slice::<u8, u32>(bytes)),
+            ItemType::U64 => format_values(bytemuck::cast_slice::<u8, u64>(bytes)),
+            ItemType::F64 => format_values(bytemuck::cast_slice::<u8, f64>(bytes)),
+        }
+    }
+}
+
+fn format_values<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(T::to_string).collect()
+}
+
+#[derive(Parser)]
+struct Args {
+    /// Store files to inspect, e.g. `data/*.store`
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Show items as numbers of this type instead of hex
+    #[arg(long = "type", value_enum)]
+    item_type: Option<ItemType>,
+
+    /// Item size in bytes for hex output
+    #[arg(long, default_value_t = 8)]
+    item_size: usize,
+
+    /// Print one JSON object per line and file
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+struct StoreStats {
+    file: String,
+    size_bytes: usize,
+    item_size: usize,
+    items: usize,
+    preview: Vec<String>,
+    /// Seconds since the Unix epoch.
+    modified: Option<u64>,
+}
+
+impl StoreStats {
+    fn read(path: &Path, item_type: Option<ItemType>, item_size: usize) -> std::io::Result<Self> {
+        let item_size = item_type.map_or(item_size, ItemType::size);
+        if item_size == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Item size must not be zero",
+            ));
+        }
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs());
+
+        let size_bytes = metadata.len() as usize;
+        let (items, preview) = if size_bytes == 0 {
+            (0, vec![])
+        } else {
+            let data = unsafe { Mmap::map(&file)? };
+            let items = data
+                .chunks_exact(item_size)
+                .rposition(|item| item.iter().any(|b| *b != 0))
+                .map_or(0, |last| last + 1);
+            let shown = &data[..items.min(PREVIEW_ITEMS) * item_size];
+            let preview = match item_type {
+                Some(item_type) => item_type.format_all(shown),
+                None => shown.chunks_exact(item_size).map(to_hex).collect(),
+            };
+            (items, preview)
+        };
+
+        Ok(Self {
+            file: path.display().to_string(),
+            size_bytes,
+            item_size,
+            items,
+            preview,
+            modified,
+        })
+    }
+
+    fn print(&self) {
+        println!("{}", self.file);
+        println!("  size:     {} bytes", self.size_bytes);
+        println!(
+            "  items:    {} of {} ({}-byte items)",
+            self.items,
+            self.size_bytes / self.item_size,
+            self.item_size
+        );
+        match self.modified {
+            Some(modified) => println!("  modified: {} (Unix time)", modified),
+            None => println!("  modified: unknown"),
+        }
+        println!("  first:    [{}]", self.preview.join(", "));
+    }
+
+    fn print_json(&self) {
+        let preview: Vec<String> = self
+            .preview
+            .iter()
+            .map(|item| format!("\"{}\"", item))
+            .collect();
+        let modified = self
+            .modified
+            .map_or("null".to_string(), |modified| modified.to_string());
+        println!(
+            "{{\"file\":\"{}\",\"size_bytes\":{},\"item_size\":{},\"items\":{},\"capacity\":{},\"modified\":{},\"first_items\":[{}]}}",
+            escape_json(&self.file),
+            self.size_bytes,
+            self.item_size,
+            self.items,
+            self.size_bytes / self.item_size,
+            modified,
+            preview.join(",")
+        );
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("0x{}", digits)
+}
+
+fn escape_json(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut failed = false;
+    for path in &args.files {
+        match StoreStats::read(path, args.item_type, args.item_size) {
+            Ok(stats) if args.json => stats.print_json(),
+            Ok(stats) => stats.print(),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
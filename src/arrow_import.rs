@@ -0,0 +1,141 @@
+use crate::engine::RodaEngine;
+use crate::journal_store::{JournalStore, JournalStoreOptions};
+use arrow::array::{ArrayData, ArrayRef, make_array};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use bytemuck::Pod;
+use std::sync::Arc;
+
+/// A column of an `ArrowSchema` type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrowField {
+    pub name: &'static str,
+    /// A fixed-width primitive type such as `DataType::UInt64` or `DataType::Float64`.
+    pub data_type: DataType,
+    /// Byte offset of the field in the struct, see `std::mem::offset_of!`.
+    pub offset: usize,
+}
+
+/// Describes how the fields of a `Pod` type map to the columns of an Arrow record batch.
+pub trait ArrowSchema: Pod {
+    fn arrow_fields() -> Vec<ArrowField>;
+}
+
+/// Creates a store from the rows of `batch`, reading each field of `T` from the column
+/// with its name.
+///
+/// # Panics
+/// Panics if a column is missing, has a different type or contains nulls, or if the
+/// store is too small, see `try_journal_store_from_arrow`.
+pub fn journal_store_from_arrow<T: Pod + Send + ArrowSchema>(
+    batch: &RecordBatch,
+    engine: &RodaEngine,
+    options: JournalStoreOptions,
+) -> JournalStore<T> {
+    match try_journal_store_from_arrow(batch, engine, options) {
+        Ok(store) => store,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Like `journal_store_from_arrow`, but returns an error instead of panicking.
+pub fn try_journal_store_from_arrow<T: Pod + Send + ArrowSchema>(
+    batch: &RecordBatch,
+    engine: &RodaEngine,
+    options: JournalStoreOptions,
+) -> Result<JournalStore<T>, ArrowError> {
+    let rows = batch.num_rows();
+    if options.size < rows {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Store is too small. Capacity: {}, Rows: {}",
+            options.size, rows
+        )));
+    }
+
+    let mut items = vec![T::zeroed(); rows];
+    let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut items);
+    for field in T::arrow_fields() {
+        let width = field_width::<T>(&field)?;
+        let column = batch
+            .column_by_name(field.name)
+            .ok_or_else(|| ArrowError::SchemaError(format!("Missing column: {}", field.name)))?;
+        if column.data_type() != &field.data_type {
+            return Err(ArrowError::SchemaError(format!(
+                "Column {} has type {}, expected {}",
+                field.name,
+                column.data_type(),
+                field.data_type
+            )));
+        }
+        if column.null_count() > 0 {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Column {} contains nulls",
+                field.name
+            )));
+        }
+
+        let data = column.to_data();
+        let start = data.offset() * width;
+        let values = &data.buffers()[0].as_slice()[start..start + rows * width];
+        for (row, value) in values.chunks_exact(width).enumerate() {
+            let at = row * size_of::<T>() + field.offset;
+            bytes[at..at + width].copy_from_slice(value);
+        }
+    }
+
+    let mut store = engine.new_journal_store::<T>(options);
+    store.bulk_append(&items);
+    Ok(store)
+}
+
+/// Converts the items of `store` into a record batch with a column for every field of `T`.
+pub fn journal_store_to_arrow<T: Pod + Send + ArrowSchema>(
+    store: &JournalStore<T>,
+) -> Result<RecordBatch, ArrowError> {
+    let items = store.items();
+    let bytes: &[u8] = bytemuck::cast_slice(items);
+    let fields = T::arrow_fields();
+
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(fields.len());
+    for field in &fields {
+        let width = field_width::<T>(field)?;
+        let mut values = Vec::with_capacity(items.len() * width);
+        for row in bytes.chunks_exact(size_of::<T>()) {
+            values.extend_from_slice(&row[field.offset..field.offset + width]);
+        }
+        let data = ArrayData::builder(field.data_type.clone())
+            .len(items.len())
+            .add_buffer(Buffer::from_slice_ref(&values))
+            .build()?;
+        columns.push(make_array(data));
+    }
+
+    let schema = Schema::new(
+        fields
+            .iter()
+            .map(|field| Field::new(field.name, field.data_type.clone(), false))
+            .collect::<Vec<_>>(),
+    );
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+/// Returns the byte width of a field, checking that it fits in `T`.
+fn field_width<T>(field: &ArrowField) -> Result<usize, ArrowError> {
+    let width = field.data_type.primitive_width().ok_or_else(|| {
+        ArrowError::SchemaError(format!(
+            "Field {} has type {}, which is not a fixed-width primitive",
+            field.name, field.data_type
+        ))
+    })?;
+    if field.offset + width > size_of::<T>() {
+        return Err(ArrowError::SchemaError(format!(
+            "Field {} at offset {} does not fit in {} bytes",
+            field.name,
+            field.offset,
+            size_of::<T>()
+        )));
+    }
+    Ok(width)
+}
@@ -0,0 +1,54 @@
+use crate::engine::RodaEngine;
+use crate::journal_store::JournalStore;
+use bytemuck::Pod;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// Lets async code append to a `JournalStore` without blocking the runtime on the store.
+///
+/// Items are sent through a bounded channel and appended by a background task running on
+/// the engine, in the order they were received. The writer can be cloned to feed the same
+/// store from several tasks; the background task finishes once every clone is dropped.
+#[derive(Clone)]
+pub struct AsyncStoreWriter<State: Pod + Send> {
+    sender: mpsc::Sender<State>,
+}
+
+impl<State: Pod + Send + 'static> AsyncStoreWriter<State> {
+    /// Spawns the task draining a channel of `capacity` items into `store` on
+    /// `tokio_runtime`, tracked as a worker of `engine`.
+    pub fn new(
+        engine: &mut RodaEngine,
+        tokio_runtime: Arc<tokio::runtime::Runtime>,
+        mut store: JournalStore<State>,
+        capacity: usize,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<State>(capacity);
+        engine.run_worker_async(tokio_runtime, async move {
+            while let Some(item) = receiver.recv().await {
+                store.append(&item);
+            }
+        });
+        Self { sender }
+    }
+
+    /// Sends an item to the store, waiting while the channel is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the background task has stopped, e.g. because the store is full.
+    pub async fn send(&self, item: State) {
+        if self.sender.send(item).await.is_err() {
+            panic!("AsyncStoreWriter task has stopped");
+        }
+    }
+
+    /// Sends an item to the store without waiting.
+    ///
+    /// Returns `TrySendError::Full` with the item if the channel is full, and
+    /// `TrySendError::Closed` if the background task has stopped.
+    pub fn try_send(&self, item: State) -> Result<(), TrySendError<State>> {
+        self.sender.try_send(item)
+    }
+}
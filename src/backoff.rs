@@ -0,0 +1,69 @@
+use crate::journal_store::StoreJournalReader;
+use bytemuck::Pod;
+use std::cell::Cell;
+use std::thread;
+use std::time::Duration;
+
+const MIN_BACKOFF: Duration = Duration::from_micros(1);
+
+/// A reader that sleeps for exponentially longer periods while the store has no new items.
+///
+/// The first miss sleeps for 1µs and every following miss doubles the sleep, up to the
+/// maximum backoff (1ms by default). A successful read resets the backoff.
+pub struct ExponentialBackoffReader<State: Pod + Send> {
+    reader: StoreJournalReader<State>,
+    backoff: Cell<Duration>,
+    max_backoff: Duration,
+    sleep_fn: Box<dyn Fn(Duration) + Send>,
+}
+
+impl<State: Pod + Send> ExponentialBackoffReader<State> {
+    pub fn new(reader: StoreJournalReader<State>) -> Self {
+        Self {
+            reader,
+            backoff: Cell::new(Duration::ZERO),
+            max_backoff: Duration::from_millis(1),
+            sleep_fn: Box::new(thread::sleep),
+        }
+    }
+
+    /// Replaces `thread::sleep`, e.g. with a function advancing a `testing::MockClock`.
+    pub fn with_sleep_fn(mut self, sleep_fn: impl Fn(Duration) + Send + 'static) -> Self {
+        self.sleep_fn = Box::new(sleep_fn);
+        self
+    }
+
+    /// Sets the longest time to sleep after a miss.
+    pub fn set_max_backoff(&mut self, max_backoff: Duration) {
+        self.max_backoff = max_backoff;
+        self.backoff.set(self.backoff.get().min(max_backoff));
+    }
+
+    /// Advances to the next item like `StoreJournalReader::next`, sleeping for the current
+    /// backoff if there is none.
+    pub fn next_with_backoff(&self) -> bool {
+        if self.reader.next() {
+            self.backoff.set(Duration::ZERO);
+            return true;
+        }
+        let backoff = (self.backoff.get() * 2)
+            .max(MIN_BACKOFF)
+            .min(self.max_backoff);
+        self.backoff.set(backoff);
+        (self.sleep_fn)(backoff);
+        false
+    }
+
+    /// Returns the time slept after the last miss, or zero if the last call found an item.
+    pub fn current_backoff(&self) -> Duration {
+        self.backoff.get()
+    }
+
+    pub fn reader(&self) -> &StoreJournalReader<State> {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> StoreJournalReader<State> {
+        self.reader
+    }
+}
@@ -1,22 +1,111 @@
 use crate::journal_store::{JournalStore, JournalStoreOptions};
+use crate::logging::{self, LogLevel, logger};
+use crate::measure::{BenchmarkResult, LatencyMeasurer};
 use crate::op_counter::OpCounter;
+#[cfg(all(feature = "profiling", unix))]
+use crate::profiling::{ProfileError, WorkerProfile};
+use crate::registry::{EngineMemorySnapshot, RegisteredStore, StoreMetadata};
+use crate::util::{adaptive_yield, resident_set_size};
 use bytemuck::Pod;
-use std::hint::spin_loop;
+use spdlog::sink::Sink;
+use spdlog::{debug, warn};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
 use std::thread;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
+/// Progress of a worker thread, used to tell when it has caught up.
+#[derive(Default)]
+struct WorkerProgress {
+    /// Number of completed steps.
+    steps: AtomicU64,
+    /// Whether the worker is between steps and its last step found no work.
+    idle: AtomicBool,
+}
+
+/// A handle to a worker owned by the engine.
+enum WorkerHandle {
+    Thread(thread::JoinHandle<()>, Arc<WorkerProgress>),
+    #[cfg(feature = "tokio")]
+    Async(tokio::task::JoinHandle<()>),
+}
+
+impl WorkerHandle {
+    /// Returns true if the worker stopped while it was expected to keep running.
+    fn is_panicked(&self, running: bool) -> bool {
+        match self {
+            WorkerHandle::Thread(handle, _) => handle.is_finished() && running,
+            // Async tasks are allowed to complete on their own.
+            #[cfg(feature = "tokio")]
+            WorkerHandle::Async(_) => false,
+        }
+    }
+
+    /// Returns true unless the worker is an async task that is still running.
+    fn is_settled(&self) -> bool {
+        match self {
+            WorkerHandle::Thread(..) => true,
+            #[cfg(feature = "tokio")]
+            WorkerHandle::Async(handle) => handle.is_finished(),
+        }
+    }
+
+    /// Returns the number of completed steps if the worker is idle: a thread between steps
+    /// whose last step found no work, or an async task that completed, which reports 0.
+    fn idle_steps(&self) -> Option<u64> {
+        match self {
+            WorkerHandle::Thread(handle, progress) => {
+                let idle = progress.idle.load(std::sync::atomic::Ordering::Acquire);
+                (idle || handle.is_finished())
+                    .then(|| progress.steps.load(std::sync::atomic::Ordering::Acquire))
+            }
+            #[cfg(feature = "tokio")]
+            WorkerHandle::Async(handle) => handle.is_finished().then_some(0),
+        }
+    }
+}
+
 /// The core execution engine for Roda.
 ///
 /// It manages worker threads, storage lifecycle, and shared operation counters.
 pub struct RodaEngine {
     root_path: &'static str,
     running: Arc<AtomicBool>,
-    worker_handlers: Vec<thread::JoinHandle<()>>,
+    draining: Arc<AtomicBool>,
+    worker_handlers: Vec<WorkerHandle>,
+    /// Background threads started by `on_memory_pressure`.
+    monitor_handlers: Vec<thread::JoinHandle<()>>,
     op_counter: Arc<OpCounter>,
     pin_cores: bool,
+    stores: HashMap<&'static str, RegisteredStore>,
+    /// Number of stores created with `new_journal_store`.
+    store_count: AtomicUsize,
+    worker_panicked: Arc<AtomicBool>,
+}
+
+/// Sequence number for worker thread names.
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Pins the current thread to a core chosen by `worker_id`.
+fn pin_to_core(worker_id: usize) {
+    if let Some(core_ids) = core_affinity::get_core_ids()
+        && let Some(core_id) = core_ids.get(worker_id % core_ids.len())
+    {
+        core_affinity::set_for_current(*core_id);
+    }
+}
+
+/// Sets the flag if the worker thread unwinds from a panic.
+struct PanicFlag(Arc<AtomicBool>);
+
+impl Drop for PanicFlag {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
 }
 
 impl RodaEngine {
@@ -25,12 +114,51 @@ impl RodaEngine {
         Self {
             root_path: "data",
             running: Arc::new(AtomicBool::new(true)),
+            draining: Arc::new(AtomicBool::new(false)),
             worker_handlers: vec![],
+            monitor_handlers: vec![],
             op_counter: OpCounter::new(),
             pin_cores: false,
+            stores: HashMap::new(),
+            store_count: AtomicUsize::new(0),
+            worker_panicked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates an engine with its own workers that shares the root path, core pinning and
+    /// operation counters with this one, so `await_idle` on either sees all operations.
+    pub(crate) fn new_sibling(&self) -> Self {
+        Self {
+            root_path: self.root_path,
+            running: Arc::new(AtomicBool::new(true)),
+            draining: Arc::new(AtomicBool::new(false)),
+            worker_handlers: vec![],
+            monitor_handlers: vec![],
+            op_counter: self.op_counter.clone(),
+            pin_cores: self.pin_cores,
+            stores: HashMap::new(),
+            store_count: AtomicUsize::new(0),
+            worker_panicked: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Sets the verbosity of the log events emitted by roda-state.
+    ///
+    /// Log events go through a roda-state logger shared by all engines, so this affects
+    /// all engines. The process-wide default `spdlog` logger is left untouched.
+    pub fn set_log_level(level: LogLevel) {
+        logging::set_level(level);
+    }
+
+    /// Routes the log events emitted by roda-state to `sink`, keeping the current level.
+    ///
+    /// Like `set_log_level`, this applies to all engines and leaves the process-wide
+    /// default `spdlog` logger untouched. Events carry structured key-value fields such as
+    /// `worker_id`, `store_name` and `op_count`.
+    pub fn set_log_sink(sink: Arc<dyn Sink>) -> spdlog::Result<()> {
+        logging::set_sink(sink)
+    }
+
     pub(crate) fn set_pin_cores(&mut self, pin_cores: bool) {
         self.pin_cores = pin_cores;
     }
@@ -40,43 +168,168 @@ impl RodaEngine {
         Self {
             root_path,
             running: Arc::new(AtomicBool::new(true)),
+            draining: Arc::new(AtomicBool::new(false)),
             worker_handlers: vec![],
+            monitor_handlers: vec![],
             op_counter: OpCounter::new(),
             pin_cores: false,
+            stores: HashMap::new(),
+            store_count: AtomicUsize::new(0),
+            worker_panicked: Arc::new(AtomicBool::new(false)),
         }
     }
 
     /// Spawns a worker thread that executes the provided runnable in a loop.
     ///
-    /// The worker will spin and yield if there is no work to do, minimizing latency.
+    /// The worker backs off with `adaptive_yield` while there is no work to do: it spins
+    /// first to keep latency low, then yields, and finally sleeps briefly when idle.
     pub fn run_worker(&mut self, mut runnable: impl FnMut() -> bool + Send + 'static) {
         let worker_id = self.worker_handlers.len();
         let running = self.running.clone();
         let pin_cores = self.pin_cores;
-        let handler = thread::spawn(move || {
-            if pin_cores
-                && let Some(core_ids) = core_affinity::get_core_ids()
-                && let Some(core_id) = core_ids.get(worker_id % core_ids.len())
-            {
-                core_affinity::set_for_current(*core_id);
+        let panic_flag = PanicFlag(self.worker_panicked.clone());
+        let draining = self.draining.clone();
+        let progress = Arc::new(WorkerProgress::default());
+        let worker_progress = progress.clone();
+        // Thread names are unique across engines, so a profile can be filtered by name
+        let name = format!(
+            "roda-{}",
+            NEXT_THREAD_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        );
+        let handler = thread::Builder::new().name(name).spawn(move || {
+            let _panic_flag = panic_flag;
+            if pin_cores {
+                pin_to_core(worker_id);
             }
 
-            let mut step_without_work_count = 0;
+            debug!(logger: logger(), "Worker started", kv: { worker_id = worker_id });
+
+            let mut spin_count = 0;
             while running.load(std::sync::atomic::Ordering::Relaxed) {
+                worker_progress
+                    .idle
+                    .store(false, std::sync::atomic::Ordering::Release);
                 let did_work = runnable();
+                worker_progress
+                    .steps
+                    .fetch_add(1, std::sync::atomic::Ordering::Release);
+                worker_progress
+                    .idle
+                    .store(!did_work, std::sync::atomic::Ordering::Release);
                 if did_work {
-                    step_without_work_count = 0;
-                } else {
-                    step_without_work_count += 1;
-                }
-                if step_without_work_count > 1000 {
+                    spin_count = 0;
+                } else if draining.load(std::sync::atomic::Ordering::Relaxed) {
+                    // Keep polling without sleeping so that the drain completes quickly
                     thread::yield_now();
-                } else if step_without_work_count > 10 {
-                    spin_loop();
+                } else {
+                    adaptive_yield(&mut spin_count);
                 }
             }
         });
-        self.worker_handlers.push(handler);
+        let handler = handler.expect("Failed to spawn worker thread");
+        self.worker_handlers
+            .push(WorkerHandle::Thread(handler, progress));
+    }
+
+    /// Runs `runnable` on a worker thread `warmup` times, then measures the latency of
+    /// another `iterations` calls.
+    ///
+    /// Every call counts as an iteration, whether or not it found work. The thread is
+    /// pinned like the engine's other workers but not tracked by the engine: it is joined
+    /// before this returns, so `runnable` may borrow from the caller.
+    pub fn bench_worker(
+        &self,
+        warmup: u64,
+        iterations: u64,
+        mut runnable: impl FnMut() -> bool + Send,
+    ) -> BenchmarkResult {
+        let worker_id = self.worker_handlers.len();
+        let pin_cores = self.pin_cores;
+        thread::scope(|scope| {
+            scope
+                .spawn(move || {
+                    if pin_cores {
+                        pin_to_core(worker_id);
+                    }
+                    for _ in 0..warmup {
+                        runnable();
+                    }
+
+                    let mut measurer = LatencyMeasurer::new(1);
+                    let start = Instant::now();
+                    for _ in 0..iterations {
+                        let _guard = measurer.measure_with_guard();
+                        runnable();
+                    }
+                    let elapsed = start.elapsed();
+                    let throughput = if elapsed.is_zero() {
+                        0.0
+                    } else {
+                        iterations as f64 / elapsed.as_secs_f64()
+                    };
+                    BenchmarkResult {
+                        latency: measurer.get_stats(),
+                        iterations,
+                        elapsed,
+                        throughput,
+                    }
+                })
+                .join()
+                .expect("Benchmarked worker panicked")
+        })
+    }
+
+    /// Spawns an async task on the given Tokio runtime and tracks it as a worker.
+    ///
+    /// Unlike thread workers, the task is not looped: it runs until its future completes.
+    /// `await_idle` waits for async workers to complete, and dropping the engine aborts
+    /// the ones that are still running.
+    #[cfg(feature = "tokio")]
+    pub fn run_worker_async(
+        &mut self,
+        tokio_runtime: Arc<tokio::runtime::Runtime>,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let handler = tokio_runtime.spawn(task);
+        self.worker_handlers.push(WorkerHandle::Async(handler));
+    }
+
+    /// Calls `callback` with the resident set size of the process in bytes when it rises
+    /// above `threshold_bytes`, e.g. to pause producers.
+    ///
+    /// The memory usage is checked every 100ms on a background thread, and the callback
+    /// runs once each time the usage crosses the threshold. The resident set size is read
+    /// from `/proc/self/status` on Linux, `task_info` on macOS and `GetProcessMemoryInfo`
+    /// on Windows. The thread is stopped together with the workers.
+    ///
+    /// Returns an `Unsupported` error if the resident set size cannot be read on this
+    /// platform.
+    pub fn on_memory_pressure(
+        &mut self,
+        threshold_bytes: usize,
+        callback: impl Fn(usize) + Send + 'static,
+    ) -> std::io::Result<()> {
+        if resident_set_size().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Reading the resident set size is not supported on this platform",
+            ));
+        }
+        let running = self.running.clone();
+        self.monitor_handlers.push(thread::spawn(move || {
+            let mut above = false;
+            while running.load(std::sync::atomic::Ordering::Relaxed) {
+                if let Some(rss) = resident_set_size() {
+                    if rss > threshold_bytes && !above {
+                        warn!(logger: logger(), "Memory pressure", kv: { rss = rss, threshold = threshold_bytes });
+                        callback(rss);
+                    }
+                    above = rss > threshold_bytes;
+                }
+                sleep(Duration::from_millis(100));
+            }
+        }));
+        Ok(())
     }
 
     /// Creates a new `JournalStore` for sequential, append-only data storage.
@@ -84,9 +337,73 @@ impl RodaEngine {
         &self,
         options: JournalStoreOptions,
     ) -> JournalStore<State> {
+        debug!(
+            logger: logger(),
+            "Journal store created",
+            kv: { store_name = options.name.as_ref(), capacity = options.size, in_memory = options.in_memory }
+        );
+        self.store_count
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         JournalStore::new(self.root_path, self.op_counter.clone(), options)
     }
 
+    /// Registers a store under `name` so its size can be inspected with `list_stores`.
+    ///
+    /// The registry does not keep the store alive; dropped stores are no longer listed.
+    /// Registering another store under the same name replaces the previous entry, and a
+    /// store has to be registered again after `extend_capacity` to report its new capacity.
+    pub fn register_store<State: Pod + Send>(
+        &mut self,
+        name: &'static str,
+        store: &JournalStore<State>,
+    ) {
+        self.stores.insert(name, store.registration());
+    }
+
+    /// Returns the metadata of all registered stores that are still alive, sorted by name.
+    pub fn list_stores(&self) -> Vec<StoreMetadata> {
+        let mut stores: Vec<StoreMetadata> = self
+            .stores
+            .iter()
+            .filter_map(|(name, store)| store.metadata(name))
+            .collect();
+        stores.sort_by_key(|s| s.name);
+        stores
+    }
+
+    /// Returns the fraction of the capacity in use of the store registered under `name`.
+    ///
+    /// Returns `None` if no store is registered under `name` or if it has been dropped.
+    pub fn store_utilisation(&self, name: &str) -> Option<f64> {
+        let (name, store) = self.stores.get_key_value(name)?;
+        store.metadata(name).map(|m| m.utilisation())
+    }
+
+    /// Returns the memory reserved by the registered stores that are still alive.
+    pub fn memory_snapshot(&self) -> EngineMemorySnapshot {
+        let mut stores: Vec<(String, usize)> = self
+            .stores
+            .iter()
+            .filter_map(|(name, store)| Some((name.to_string(), store.memory_usage()?)))
+            .collect();
+        stores.sort();
+        let total_bytes = stores.iter().map(|(_, bytes)| bytes).sum();
+        EngineMemorySnapshot {
+            stores,
+            total_bytes,
+        }
+    }
+
+    /// Returns the flag raised when a worker thread panics, and the registered stores.
+    pub(crate) fn health_sources(&self) -> (Arc<AtomicBool>, Vec<(&'static str, RegisteredStore)>) {
+        let stores = self
+            .stores
+            .iter()
+            .map(|(name, store)| (*name, store.clone()))
+            .collect();
+        (self.worker_panicked.clone(), stores)
+    }
+
     /// Blocks until the engine is idle (i.e., no operations have occurred for a short period).
     pub fn await_idle(&self, timeout: Duration) {
         let start = Instant::now();
@@ -94,23 +411,156 @@ impl RodaEngine {
         loop {
             sleep(Duration::from_millis(1));
             let new_op_count = self.op_counter.total_op_count();
-            if new_op_count == last_op_count {
+            if new_op_count == last_op_count && self.is_async_settled() {
                 break;
             }
             if start.elapsed() > timeout {
+                warn!(logger: logger(), "Timed out waiting for the engine to become idle", kv: { op_count = new_op_count });
                 break;
             }
             last_op_count = new_op_count;
         }
     }
 
-    pub fn is_any_worker_panicked(&self) -> bool {
-        for handler in &self.worker_handlers {
-            if handler.is_finished() && self.running.load(std::sync::atomic::Ordering::Relaxed) {
-                return true;
+    /// Blocks until the engine is idle like `await_idle`, calling `on_tick` with the total
+    /// operation count every `check_interval` while waiting.
+    pub fn await_idle_with_progress(
+        &self,
+        timeout: Duration,
+        check_interval: Duration,
+        on_tick: impl Fn(u64),
+    ) {
+        let start = Instant::now();
+        let mut last_tick = start;
+        let mut last_op_count = self.total_ops();
+        loop {
+            sleep(Duration::from_millis(1));
+            let new_op_count = self.total_ops();
+            if last_tick.elapsed() >= check_interval {
+                on_tick(new_op_count);
+                last_tick = Instant::now();
+            }
+            if new_op_count == last_op_count && self.is_async_settled() {
+                break;
+            }
+            if start.elapsed() > timeout {
+                warn!(logger: logger(), "Timed out waiting for the engine to become idle", kv: { op_count = new_op_count });
+                break;
             }
+            last_op_count = new_op_count;
         }
-        false
+    }
+
+    fn is_async_settled(&self) -> bool {
+        self.worker_handlers.iter().all(WorkerHandle::is_settled)
+    }
+
+    /// Stops the engine after its workers have processed all available items.
+    ///
+    /// Workers stop backing off while draining. The engine is considered drained when, in
+    /// two consecutive checks, no reader made progress and every worker completed a step
+    /// that found no work. The workers are then stopped and joined, also if `timeout`
+    /// elapses first. Returns true if all items were processed before the timeout.
+    pub fn stop_and_drain(&mut self, timeout: Duration) -> bool {
+        self.draining
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let start = Instant::now();
+        let mut last_op_count = self.total_ops();
+        let mut last_steps: Vec<Option<u64>> = vec![None; self.worker_handlers.len()];
+        let mut quiet_checks = 0;
+        let drained = loop {
+            sleep(Duration::from_millis(1));
+            let op_count = self.total_ops();
+            let steps: Vec<Option<u64>> = self
+                .worker_handlers
+                .iter()
+                .map(WorkerHandle::idle_steps)
+                .collect();
+            // Completed async tasks report 0 steps and have nothing left to do
+            let all_stepped_idle = steps.iter().zip(&last_steps).all(|(now, before)| {
+                matches!((now, before), (Some(now), Some(before)) if now > before || *now == 0)
+            });
+            if op_count == last_op_count && all_stepped_idle {
+                quiet_checks += 1;
+                if quiet_checks == 2 {
+                    break true;
+                }
+            } else {
+                quiet_checks = 0;
+            }
+            if start.elapsed() > timeout {
+                warn!(logger: logger(), "Timed out draining the engine", kv: { op_count = op_count });
+                break false;
+            }
+            last_op_count = op_count;
+            last_steps = steps;
+        };
+        self.stop_workers();
+        drained
+    }
+
+    /// Stops and joins all workers.
+    fn stop_workers(&mut self) {
+        self.running
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        for handler in self.worker_handlers.drain(..) {
+            match handler {
+                WorkerHandle::Thread(handler, _) => handler.join().unwrap(),
+                #[cfg(feature = "tokio")]
+                WorkerHandle::Async(handler) => handler.abort(),
+            }
+        }
+        for handler in self.monitor_handlers.drain(..) {
+            handler.join().unwrap();
+        }
+    }
+
+    /// Returns the total number of operations performed by all readers of this engine.
+    pub fn total_ops(&self) -> u64 {
+        self.op_counter.total_op_count()
+    }
+
+    /// Samples the call stacks of the worker started as the `worker_id`-th `run_worker`
+    /// call for `duration`, blocking meanwhile.
+    ///
+    /// This uses a `SIGPROF` based profiler for the whole process, so only one profile can
+    /// be taken at a time. Only available on Unix with the `profiling` feature.
+    #[cfg(all(feature = "profiling", unix))]
+    pub fn profile_worker(
+        &self,
+        worker_id: usize,
+        duration: Duration,
+    ) -> Result<WorkerProfile, ProfileError> {
+        let thread_name = match self.worker_handlers.get(worker_id) {
+            Some(WorkerHandle::Thread(handle, _)) => handle.thread().name().map(str::to_string),
+            _ => None,
+        }
+        .ok_or(ProfileError::UnknownWorker(worker_id))?;
+        WorkerProfile::record(&thread_name, duration)
+    }
+
+    /// Returns the number of stores created with `new_journal_store`.
+    pub fn store_count(&self) -> usize {
+        self.store_count.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Returns the number of workers owned by the engine, including finished ones.
+    ///
+    /// Stopped workers are no longer counted after `stop_and_drain`.
+    pub fn worker_count(&self) -> usize {
+        self.worker_handlers.len()
+    }
+
+    /// Returns true until the workers are stopped with `stop_and_drain`.
+    pub fn is_running(&self) -> bool {
+        self.running.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn is_any_worker_panicked(&self) -> bool {
+        let running = self.running.load(std::sync::atomic::Ordering::Relaxed);
+        self.worker_handlers
+            .iter()
+            .any(|handler| handler.is_panicked(running))
     }
 }
 
@@ -122,10 +572,6 @@ impl Default for RodaEngine {
 
 impl Drop for RodaEngine {
     fn drop(&mut self) {
-        self.running
-            .store(false, std::sync::atomic::Ordering::Relaxed);
-        for handler in self.worker_handlers.drain(..) {
-            handler.join().unwrap();
-        }
+        self.stop_workers();
     }
 }
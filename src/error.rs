@@ -0,0 +1,30 @@
+use std::fmt;
+
+/// Errors returned by the fallible operations of the engine and its stores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RodaError {
+    /// The store has no room for another item. Sizes are in bytes.
+    StoreFull {
+        capacity: usize,
+        position: usize,
+        item_size: usize,
+    },
+}
+
+impl fmt::Display for RodaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RodaError::StoreFull {
+                capacity,
+                position,
+                item_size,
+            } => write!(
+                f,
+                "Store is full. Capacity: {}, Current position: {}, State size: {}",
+                capacity, position, item_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RodaError {}
@@ -0,0 +1,211 @@
+use crate::engine::RodaEngine;
+use crate::registry::{RegisteredStore, StoreMetadata};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+struct StoreFullCallback {
+    threshold: f64,
+    callback: Box<dyn Fn(&str) + Send>,
+    /// Stores currently above the threshold, so the callback fires once per crossing.
+    above: Vec<&'static str>,
+}
+
+struct HealthState {
+    worker_panicked: Arc<AtomicBool>,
+    stores: Vec<(&'static str, RegisteredStore)>,
+    on_worker_panic: Mutex<Vec<Box<dyn Fn() + Send>>>,
+    on_store_full: Mutex<Vec<StoreFullCallback>>,
+}
+
+impl HealthState {
+    fn stores(&self) -> impl Iterator<Item = StoreMetadata> + '_ {
+        self.stores
+            .iter()
+            .filter_map(|(name, store)| store.metadata(name))
+    }
+
+    fn is_healthy(&self) -> bool {
+        !self.worker_panicked.load(Ordering::Relaxed)
+            && self.stores().all(|store| store.count < store.capacity)
+    }
+
+    /// Runs the callbacks for the conditions that started since the last check.
+    fn check(&self, panic_reported: &mut bool) {
+        if !*panic_reported && self.worker_panicked.load(Ordering::Relaxed) {
+            *panic_reported = true;
+            for callback in self.on_worker_panic.lock().unwrap().iter() {
+                callback();
+            }
+        }
+
+        let stores: Vec<StoreMetadata> = self.stores().collect();
+        for entry in self.on_store_full.lock().unwrap().iter_mut() {
+            for store in &stores {
+                let is_above = store.utilisation() >= entry.threshold;
+                let was_above = entry.above.contains(&store.name);
+                if is_above && !was_above {
+                    entry.above.push(store.name);
+                    (entry.callback)(store.name);
+                } else if !is_above && was_above {
+                    entry.above.retain(|name| *name != store.name);
+                }
+            }
+        }
+    }
+}
+
+/// Monitors the workers and registered stores of a `RodaEngine`.
+///
+/// The stores registered with `RodaEngine::register_store` at the time of creation are
+/// monitored; stores registered after `new` are not picked up. Callbacks run on the
+/// monitor thread started by `start`, once each time a condition starts to hold.
+pub struct HealthCheck {
+    state: Arc<HealthState>,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl HealthCheck {
+    pub fn new(engine: &RodaEngine) -> Self {
+        let (worker_panicked, stores) = engine.health_sources();
+        Self {
+            state: Arc::new(HealthState {
+                worker_panicked,
+                stores,
+                on_worker_panic: Mutex::new(vec![]),
+                on_store_full: Mutex::new(vec![]),
+            }),
+            running: Arc::new(AtomicBool::new(true)),
+            handles: vec![],
+        }
+    }
+
+    /// Registers a callback that is called when a worker thread of the engine panics.
+    pub fn on_worker_panic(&self, callback: impl Fn() + Send + 'static) {
+        self.state
+            .on_worker_panic
+            .lock()
+            .unwrap()
+            .push(Box::new(callback));
+    }
+
+    /// Registers a callback that is called with the store name when the utilisation of a
+    /// store reaches `threshold`.
+    pub fn on_store_full(&self, threshold: f64, callback: impl Fn(&str) + Send + 'static) {
+        self.state
+            .on_store_full
+            .lock()
+            .unwrap()
+            .push(StoreFullCallback {
+                threshold,
+                callback: Box::new(callback),
+                above: vec![],
+            });
+    }
+
+    /// Spawns a thread that checks the engine every `interval` and runs the callbacks.
+    ///
+    /// The thread is stopped when the health check is dropped.
+    pub fn start(&mut self, interval: Duration) {
+        let state = self.state.clone();
+        let running = self.running.clone();
+        self.handles.push(thread::spawn(move || {
+            let mut panic_reported = false;
+            while running.load(Ordering::Relaxed) {
+                state.check(&mut panic_reported);
+                thread::sleep(interval);
+            }
+        }));
+    }
+
+    /// Returns true if no worker has panicked and no monitored store is full.
+    pub fn is_healthy(&self) -> bool {
+        self.state.is_healthy()
+    }
+
+    /// Serves the health status as JSON on `http://{addr}/health`.
+    ///
+    /// Pass `("127.0.0.1", port)` to only accept local connections. Responds with `200 OK`
+    /// when healthy and `503 Service Unavailable` otherwise. The server is stopped when the
+    /// health check is dropped.
+    #[cfg(feature = "http-health")]
+    pub fn http_endpoint(&mut self, addr: impl std::net::ToSocketAddrs) -> std::io::Result<()> {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let state = self.state.clone();
+        let running = self.running.clone();
+        self.handles.push(thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                let mut stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(_) => {
+                        thread::sleep(Duration::from_millis(10));
+                        continue;
+                    }
+                };
+                let _ = stream.set_nonblocking(false);
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+                let mut request = [0u8; 1024];
+                let len = stream.read(&mut request).unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..len]);
+
+                let response = if request.starts_with("GET /health ") {
+                    let healthy = state.is_healthy();
+                    let status = if healthy {
+                        "200 OK"
+                    } else {
+                        "503 Service Unavailable"
+                    };
+                    let body = health_json(&state, healthy);
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        }));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "http-health")]
+fn health_json(state: &HealthState, healthy: bool) -> String {
+    let stores: Vec<String> = state
+        .stores()
+        .map(|store| {
+            format!(
+                "{{\"name\":\"{}\",\"count\":{},\"capacity\":{},\"utilisation\":{}}}",
+                store.name,
+                store.count,
+                store.capacity,
+                store.utilisation()
+            )
+        })
+        .collect();
+    format!(
+        "{{\"healthy\":{},\"worker_panicked\":{},\"stores\":[{}]}}",
+        healthy,
+        state.worker_panicked.load(Ordering::Relaxed),
+        stores.join(",")
+    )
+}
+
+impl Drop for HealthCheck {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            handle.join().unwrap();
+        }
+    }
+}
@@ -1,17 +1,24 @@
 use crate::components::{Appendable, IterativeReadable};
+use crate::error::RodaError;
 use crate::op_counter::OpCounter;
+use crate::registry::RegisteredStore;
 use crate::storage::journal_mmap::JournalMmap;
+use crate::util::adaptive_yield;
 use bytemuck::Pod;
+use std::borrow::Cow;
 use std::cell::Cell;
+use std::cmp::Ordering;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::atomic::AtomicU64;
-use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::mpsc::{Receiver, sync_channel};
+use std::thread::{self, JoinHandle};
 
 /// Configuration options for a `JournalStore`.
 pub struct JournalStoreOptions {
     /// The name of the store, used for the filename.
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
     /// The maximum number of items the store can hold.
     pub size: usize,
     /// Whether to keep the store only in memory.
@@ -22,6 +29,9 @@ pub struct JournalStoreOptions {
 pub struct JournalStore<State: Pod + Send> {
     storage: JournalMmap,
     op_counter: Arc<OpCounter>,
+    last_appended: Option<State>,
+    /// Cleared when the store is dropped, so readers know no more items will arrive.
+    open: Arc<AtomicBool>,
     _marker: std::marker::PhantomData<State>,
 }
 
@@ -30,6 +40,7 @@ pub struct StoreJournalReader<State: Pod + Send> {
     next_index: Cell<usize>,
     storage: JournalMmap,
     op_count: Arc<AtomicU64>,
+    store_open: Arc<AtomicBool>,
     _marker: std::marker::PhantomData<State>,
 }
 
@@ -54,22 +65,175 @@ impl<State: Pod + Send> JournalStore<State> {
         Self {
             op_counter,
             storage,
+            last_appended: None,
+            open: Arc::new(AtomicBool::new(true)),
             _marker: Default::default(),
         }
     }
 
+    /// Creates a store holding `f(i)` for every `i` in `0..count`, e.g. to set up tests.
+    ///
+    /// The store is not created by an engine, so reads are not counted by any engine's
+    /// `total_ops`. File-backed stores are put in `root_path`, which must exist; it is
+    /// ignored for in-memory stores.
+    ///
+    /// # Panics
+    /// Panics if `options.size` is less than `count`.
+    pub fn from_fn(
+        root_path: &'static str,
+        count: usize,
+        mut f: impl FnMut(usize) -> State,
+        options: JournalStoreOptions,
+    ) -> Self {
+        let mut store = Self::with_room_for(root_path, count, options);
+        for i in 0..count {
+            store.append(&f(i));
+        }
+        store
+    }
+
+    /// Creates a store holding `items`, see `from_fn`.
+    ///
+    /// # Panics
+    /// Panics if `options.size` is less than the number of items.
+    pub fn from_slice(
+        root_path: &'static str,
+        items: &[State],
+        options: JournalStoreOptions,
+    ) -> Self {
+        let mut store = Self::with_room_for(root_path, items.len(), options);
+        store.bulk_append(items);
+        store
+    }
+
+    fn with_room_for(root_path: &'static str, count: usize, options: JournalStoreOptions) -> Self {
+        assert!(
+            options.size >= count,
+            "Store is too small. Capacity: {}, Items: {}",
+            options.size,
+            count
+        );
+        Self::new(root_path, OpCounter::new(), options)
+    }
+
     /// Appends an item to the store.
+    ///
+    /// # Panics
+    /// Panics if the store is full, see `try_append`.
     pub fn append(&mut self, state: &State) {
+        if let Err(e) = self.try_append(state) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Appends an item to the store, or returns `RodaError::StoreFull` if there is no room.
+    pub fn try_append(&mut self, state: &State) -> Result<(), RodaError> {
         let size = size_of::<State>();
         let current_pos = self.storage.get_write_index();
+        if current_pos + size > self.storage.len() {
+            return Err(RodaError::StoreFull {
+                capacity: self.storage.len(),
+                position: current_pos,
+                item_size: size,
+            });
+        }
+        self.storage.append(state);
+        self.last_appended = Some(*state);
+        Ok(())
+    }
+
+    /// Appends all items at once. Readers see either none or all of them.
+    pub fn bulk_append(&mut self, items: &[State]) {
+        let size = size_of_val(items);
+        let current_pos = self.storage.get_write_index();
         assert!(
             current_pos + size <= self.storage.len(),
-            "Store is full. Capacity: {}, Current position: {}, State size: {}",
+            "Store is full. Capacity: {}, Current position: {}, Batch size: {}",
             self.storage.len(),
             current_pos,
             size
         );
-        self.storage.append(state);
+        self.storage.append_slice(items);
+        if let Some(last) = items.last() {
+            self.last_appended = Some(*last);
+        }
+    }
+
+    /// Appends an item only if `changed(last, state)` returns true for the last appended item.
+    ///
+    /// The first item is always appended. Returns whether the item was appended.
+    pub fn append_if_changed(
+        &mut self,
+        state: &State,
+        changed: impl Fn(&State, &State) -> bool,
+    ) -> bool {
+        if let Some(last) = &self.last_appended
+            && !changed(last, state)
+        {
+            return false;
+        }
+        self.append(state);
+        true
+    }
+
+    /// Grows the store to hold `new_size` items without losing appended data.
+    ///
+    /// The memory is remapped, so this fails with `ErrorKind::ResourceBusy` while readers of
+    /// the store exist. Create readers after extending.
+    pub fn extend_capacity(&mut self, new_size: usize) -> std::io::Result<()> {
+        self.storage.extend(new_size * size_of::<State>())
+    }
+
+    /// Returns a new in-memory store with the same capacity holding the items for which
+    /// `predicate` returns true. This store is left unchanged.
+    pub fn compact(&self, predicate: impl Fn(&State) -> bool) -> JournalStore<State> {
+        let mut storage = JournalMmap::new(None, self.storage.len()).unwrap();
+        let last_appended = self.copy_matching(&mut storage, predicate);
+        JournalStore {
+            storage,
+            op_counter: self.op_counter.clone(),
+            last_appended,
+            open: Arc::new(AtomicBool::new(true)),
+            _marker: Default::default(),
+        }
+    }
+
+    /// Removes the items for which `predicate` returns false.
+    ///
+    /// A file-backed store writes the remaining items to a temporary file, which then
+    /// atomically replaces the store file. Like `extend_capacity`, this fails with
+    /// `ErrorKind::ResourceBusy` while readers of the store exist.
+    pub fn compact_in_place(&mut self, predicate: impl Fn(&State) -> bool) -> std::io::Result<()> {
+        self.storage.ensure_no_readers()?;
+        let mut storage = match self.storage.path() {
+            Some(path) => JournalMmap::new(
+                Some(path.with_extension("store.compact")),
+                self.storage.len(),
+            )?,
+            None => JournalMmap::new(None, self.storage.len())?,
+        };
+        let last_appended = self.copy_matching(&mut storage, predicate);
+        if let Some(path) = self.storage.path() {
+            storage.flush_range(0, storage.len())?;
+            storage.rename(path.clone())?;
+        }
+        self.storage.replace_with(storage);
+        self.last_appended = last_appended;
+        Ok(())
+    }
+
+    /// Appends the items matching `predicate` to `storage` and returns the last of them.
+    fn copy_matching(
+        &self,
+        storage: &mut JournalMmap,
+        predicate: impl Fn(&State) -> bool,
+    ) -> Option<State> {
+        let mut last = None;
+        for item in self.items().iter().filter(|item| predicate(item)) {
+            storage.append(item);
+            last = Some(*item);
+        }
+        last
     }
 
     pub fn reader(&self) -> StoreJournalReader<State> {
@@ -77,6 +241,7 @@ impl<State: Pod + Send> JournalStore<State> {
             op_count: self.op_counter.new_counter(),
             next_index: Cell::new(0),
             storage: self.storage.reader(),
+            store_open: self.open.clone(),
             _marker: Default::default(),
         }
     }
@@ -84,6 +249,79 @@ impl<State: Pod + Send> JournalStore<State> {
     pub fn size(&self) -> usize {
         self.storage.get_write_index() / size_of::<State>()
     }
+
+    /// Returns the number of bytes reserved for the store, whether used or not.
+    pub fn memory_usage(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Calls `handler` for every item from `start_index` up to the current write position.
+    ///
+    /// Does nothing if `start_index` is past the last item.
+    pub fn for_each_from(&self, start_index: usize, handler: impl FnMut(&State)) {
+        self.for_each_from_to(start_index, usize::MAX, handler);
+    }
+
+    /// Calls `handler` for every item in `start..end`, clamping `end` to the current write
+    /// position.
+    ///
+    /// Does nothing if `start` is past the last item.
+    pub fn for_each_from_to(&self, start: usize, end: usize, mut handler: impl FnMut(&State)) {
+        let end = end.min(self.size());
+        if start >= end {
+            return;
+        }
+        let window = self
+            .storage
+            .read_window::<State>(start * size_of::<State>(), end - start);
+        for item in window {
+            handler(item);
+        }
+    }
+
+    /// Binary searches the appended items, which must be sorted consistently with `f`.
+    ///
+    /// Returns the index of a matching item, or `None` if there is none. If several items
+    /// match, any one of them may be returned.
+    pub fn binary_search_by(&self, f: impl Fn(&State) -> Ordering) -> Option<usize> {
+        self.items().binary_search_by(f).ok()
+    }
+
+    /// Returns the index of the first item with a timestamp of at least `ts`, for items
+    /// appended in timestamp order, or `None` if all items are earlier.
+    pub fn find_first_at_or_after(&self, ts: u64, ts_fn: impl Fn(&State) -> u64) -> Option<usize> {
+        let index = self.items().partition_point(|item| ts_fn(item) < ts);
+        (index < self.size()).then_some(index)
+    }
+
+    pub(crate) fn registration(&self) -> RegisteredStore {
+        RegisteredStore {
+            write_index: self.storage.write_index_weak(),
+            capacity: self.storage.len() / size_of::<State>(),
+            element_size: size_of::<State>(),
+            memory_usage: self.storage.len(),
+        }
+    }
+
+    /// Returns the file of a file-backed store.
+    pub(crate) fn path(&self) -> Option<&PathBuf> {
+        self.storage.path()
+    }
+
+    /// Returns all appended items.
+    pub(crate) fn items(&self) -> &[State] {
+        self.storage.read_window::<State>(0, self.size())
+    }
+
+    pub(crate) fn last_appended(&self) -> Option<&State> {
+        self.last_appended.as_ref()
+    }
+}
+
+impl<State: Pod + Send> Drop for JournalStore<State> {
+    fn drop(&mut self) {
+        self.open.store(false, Release);
+    }
 }
 
 impl<State: Pod + Send> Appendable<State> for JournalStore<State> {
@@ -93,6 +331,25 @@ impl<State: Pod + Send> Appendable<State> for JournalStore<State> {
 }
 
 impl<State: Pod + Send> StoreJournalReader<State> {
+    /// Creates another reader of the same store, positioned at its first item.
+    ///
+    /// The new reader reports its reads to the same operation counter.
+    pub(crate) fn clone_from_start(&self) -> Self {
+        Self {
+            next_index: Cell::new(0),
+            storage: self.storage.reader(),
+            op_count: self.op_count.clone(),
+            store_open: self.store_open.clone(),
+            _marker: Default::default(),
+        }
+    }
+
+    /// Returns whether the store is still alive, i.e. whether more items may be appended.
+    #[inline(always)]
+    pub fn is_store_open(&self) -> bool {
+        self.store_open.load(Acquire)
+    }
+
     #[inline(always)]
     pub fn next(&self) -> bool {
         let index_to_read = self.next_index.get();
@@ -109,6 +366,20 @@ impl<State: Pod + Send> StoreJournalReader<State> {
         true
     }
 
+    /// Advances to the next item like `next`, backing off with `adaptive_yield` if there is none.
+    ///
+    /// `spin` tracks the idle iterations and is reset when an item is available.
+    #[inline(always)]
+    pub fn next_or_yield(&self, spin: &mut u32) -> bool {
+        if self.next() {
+            *spin = 0;
+            true
+        } else {
+            adaptive_yield(spin);
+            false
+        }
+    }
+
     #[inline(always)]
     pub fn get_index(&self) -> usize {
         self.next_index.get()
@@ -129,17 +400,25 @@ impl<State: Pod + Send> StoreJournalReader<State> {
     ///
     /// This is highly optimized using batch reading (read_window).
     #[inline(always)]
-    pub fn handle_remaining(&self, mut handler: impl FnMut(&State)) -> usize {
+    pub fn handle_remaining(&self, handler: impl FnMut(&State)) -> usize {
+        self.handle_remaining_up_to(usize::MAX, handler)
+    }
+
+    /// Processes at most `max` remaining items in the store using the provided handler.
+    ///
+    /// Items beyond `max` stay unread and are picked up by the next call.
+    #[inline(always)]
+    pub fn handle_remaining_up_to(&self, max: usize, mut handler: impl FnMut(&State)) -> usize {
         let index_to_read = self.next_index.get();
         let offset = index_to_read * size_of::<State>();
         let write_index = self.storage.get_write_index();
 
         // If there is no new data, exit immediately (Hot path)
-        if offset + size_of::<State>() > write_index {
+        if offset + size_of::<State>() > write_index || max == 0 {
             return 0;
         }
 
-        let processed_items = (write_index - offset) / size_of::<State>();
+        let processed_items = ((write_index - offset) / size_of::<State>()).min(max);
 
         let window = self.storage.read_window::<State>(offset, processed_items);
 
@@ -184,6 +463,19 @@ impl<State: Pod + Send> StoreJournalReader<State> {
         self.with_at(at, |s| *s)
     }
 
+    /// Returns the item `next` would advance to, without advancing the cursor.
+    #[inline(always)]
+    pub fn peek(&self) -> Option<State> {
+        self.peek_at(0)
+    }
+
+    /// Returns the item `offset` positions after the one `next` would advance to,
+    /// without advancing the cursor.
+    #[inline(always)]
+    pub fn peek_at(&self, offset: usize) -> Option<State> {
+        self.get_at(self.next_index.get() + offset)
+    }
+
     #[inline(always)]
     pub fn get_last(&self) -> Option<State> {
         self.with_last(|s| *s)
@@ -206,6 +498,36 @@ impl<State: Pod + Send> StoreJournalReader<State> {
     }
 }
 
+impl<State: Pod + Send + 'static> StoreJournalReader<State> {
+    /// Moves the reader to a thread that sends every remaining item to a channel with room
+    /// for `buffer` items.
+    ///
+    /// The thread stops once the store has been dropped and all of its items were sent, or
+    /// when the receiver is dropped. Join the returned handle to wait until all items have
+    /// been sent.
+    pub fn into_channel(self, buffer: usize) -> (Receiver<State>, JoinHandle<()>) {
+        let (sender, receiver) = sync_channel(buffer);
+        let handle = thread::spawn(move || {
+            let mut spin = 0;
+            loop {
+                // Checked before reading, so items appended before the drop are still sent
+                let open = self.is_store_open();
+                if self.next() {
+                    spin = 0;
+                    if sender.send(self.get().unwrap()).is_err() {
+                        return;
+                    }
+                } else if !open {
+                    return;
+                } else {
+                    adaptive_yield(&mut spin);
+                }
+            }
+        });
+        (receiver, handle)
+    }
+}
+
 impl<State: Pod + Send> IterativeReadable<State> for StoreJournalReader<State> {
     fn next(&self) -> bool {
         self.next()
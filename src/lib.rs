@@ -3,20 +3,64 @@
 //! It enables building deterministic streaming pipelines with cache-friendly dataflows,
 //! wait-free reads, and explicit memory bounds.
 
+#[cfg(feature = "arrow")]
+mod arrow_import;
+#[cfg(feature = "tokio")]
+mod async_writer;
+mod backoff;
 mod components;
 mod engine;
+mod error;
+mod health;
 mod journal_store;
+mod logging;
 mod macros;
+mod math;
 pub mod measure;
+#[cfg(feature = "prometheus")]
+mod metrics;
 mod op_counter;
 mod pipe;
+#[cfg(all(feature = "profiling", unix))]
+mod profiling;
+mod registry;
+mod replay;
+mod ring_buffer;
+mod segment_index;
 mod stage;
 mod stage_engine;
 mod storage;
+pub mod testing;
+mod tick_store;
+mod topology;
+mod util;
 
+#[cfg(feature = "arrow")]
+pub use crate::arrow_import::{
+    ArrowField, ArrowSchema, journal_store_from_arrow, journal_store_to_arrow,
+    try_journal_store_from_arrow,
+};
+#[cfg(feature = "tokio")]
+pub use crate::async_writer::AsyncStoreWriter;
+pub use crate::backoff::ExponentialBackoffReader;
 pub use crate::components::*;
 pub use crate::engine::RodaEngine;
+pub use crate::error::RodaError;
+pub use crate::health::HealthCheck;
 pub use crate::journal_store::{JournalStore, JournalStoreOptions, StoreJournalReader};
+pub use crate::logging::LogLevel;
+pub use crate::math::{Correlation, CorrelationMatrix, QuantileTracker, quantile_stage};
+#[cfg(feature = "prometheus")]
+pub use crate::metrics::collect_all_metrics;
 pub use crate::pipe::*;
-pub use crate::stage::{OutputCollector, Stage, StageExt};
-pub use crate::stage_engine::StageEngine;
+#[cfg(all(feature = "profiling", unix))]
+pub use crate::profiling::{ProfileError, StackTrace, WorkerProfile};
+pub use crate::registry::{EngineMemorySnapshot, StoreMetadata};
+pub use crate::replay::ReplayEngine;
+pub use crate::ring_buffer::RingBuffer;
+pub use crate::segment_index::{SegmentIndex, SegmentMeta};
+pub use crate::stage::{OutputCollector, Stage, StageExt, StageOutput};
+pub use crate::stage_engine::{OutputIter, PipelineReport, StageEngine, StageReport};
+pub use crate::tick_store::TickStore;
+pub use crate::topology::{Waterfall, WaterfallLayer};
+pub use crate::util::adaptive_yield;
@@ -0,0 +1,63 @@
+use spdlog::sink::Sink;
+use spdlog::{Level, LevelFilter, Logger};
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// The logger of roda-state, kept apart from the process-wide default `spdlog` logger.
+///
+/// Starts as a fork of the default logger, so events go to the same sinks until
+/// `RodaEngine::set_log_sink` is called.
+static LOGGER: LazyLock<RwLock<Arc<Logger>>> = LazyLock::new(|| {
+    let logger = spdlog::default_logger()
+        .fork_with_name(Some("roda-state"))
+        .expect("Failed to create the roda-state logger");
+    RwLock::new(logger)
+});
+
+/// Returns the logger that all log events of roda-state go through.
+pub(crate) fn logger() -> Arc<Logger> {
+    LOGGER.read().unwrap().clone()
+}
+
+pub(crate) fn set_level(level: LogLevel) {
+    logger().set_level_filter(level.level_filter());
+}
+
+/// Replaces the roda-state logger with one writing to `sink`, keeping the current level.
+pub(crate) fn set_sink(sink: Arc<dyn Sink>) -> spdlog::Result<()> {
+    let mut current = LOGGER.write().unwrap();
+    let logger = Logger::builder()
+        .name("roda-state")
+        .sink(sink)
+        .level_filter(current.level_filter())
+        .build()?;
+    *current = Arc::new(logger);
+    Ok(())
+}
+
+/// Verbosity of the log events emitted by roda-state.
+///
+/// Each level includes the events of all more severe levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Off,
+    Critical,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub(crate) fn level_filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Critical => LevelFilter::MoreSevereEqual(Level::Critical),
+            LogLevel::Error => LevelFilter::MoreSevereEqual(Level::Error),
+            LogLevel::Warn => LevelFilter::MoreSevereEqual(Level::Warn),
+            LogLevel::Info => LevelFilter::MoreSevereEqual(Level::Info),
+            LogLevel::Debug => LevelFilter::MoreSevereEqual(Level::Debug),
+            LogLevel::Trace => LevelFilter::All,
+        }
+    }
+}
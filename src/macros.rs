@@ -8,3 +8,41 @@ macro_rules! pipe {
         }
     };
 }
+
+/// Checks at compile time that a chain of stages fits together.
+///
+/// `assert_pipeline_types!(In, stage_a => Mid, stage_b => Out)` asserts that `stage_a`
+/// implements `Stage<In, Mid>` and `stage_b` implements `Stage<Mid, Out>`. Each stage gets
+/// its own `_check_types` function, so a mismatch is reported against that stage with the
+/// expected input and output types instead of deep inside the `Pipeline` produced by `pipe!`.
+/// The functions are never called and no runtime code is generated.
+///
+/// The stage expressions are only type checked, so they may not refer to local variables.
+///
+/// ```compile_fail
+/// use roda_state::assert_pipeline_types;
+///
+/// // The second stage expects `u64` but the first one produces `u32`.
+/// assert_pipeline_types!(
+///     u32,
+///     |x: &u32| Some(*x * 2) => u32,
+///     |x: &u64| Some(*x as u32) => u32,
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_pipeline_types {
+    (@check $in:ty; $stage:expr => $out:ty $(, $rest:expr => $rest_out:ty)*) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn _check_types() {
+                fn assert_stage<S: $crate::Stage<$in, $out>>(_: S) {}
+                assert_stage($stage);
+            }
+        };
+        $crate::assert_pipeline_types!(@check $out; $($rest => $rest_out),*);
+    };
+    (@check $in:ty;) => {};
+    ($in:ty, $($stage:expr => $out:ty),+ $(,)?) => {
+        $crate::assert_pipeline_types!(@check $in; $($stage => $out),+);
+    };
+}
@@ -0,0 +1,544 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Rolling Pearson correlation between two series over the last `window` pairs.
+pub struct Correlation {
+    matrix: CorrelationMatrix<2>,
+}
+
+impl Correlation {
+    pub fn new(window: usize) -> Self {
+        Self {
+            matrix: CorrelationMatrix::new(window),
+        }
+    }
+
+    /// Adds a pair and returns the correlation of the pairs currently in the window.
+    ///
+    /// Returns `NaN` while fewer than two pairs were seen or when either series is constant.
+    pub fn update(&mut self, x: f64, y: f64) -> f64 {
+        self.matrix.update([x, y])[0][1]
+    }
+
+    /// Turns the correlation into a stage that passes the correlation of `x_fn` and `y_fn`
+    /// to `out_fn` together with each item.
+    pub fn as_stage<T: Pod + Send>(
+        self,
+        x_fn: impl Fn(&T) -> f64 + Send,
+        y_fn: impl Fn(&T) -> f64 + Send,
+        out_fn: impl Fn(f64, &T) -> T + Send,
+    ) -> impl Stage<T, T> + Send {
+        CorrelationStage {
+            correlation: self,
+            x_fn,
+            y_fn,
+            out_fn,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+struct CorrelationStage<T, X, Y, O> {
+    correlation: Correlation,
+    x_fn: X,
+    y_fn: Y,
+    out_fn: O,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, X, Y, O> Stage<T, T> for CorrelationStage<T, X, Y, O>
+where
+    T: Pod + Send,
+    X: Fn(&T) -> f64,
+    Y: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let r = self
+            .correlation
+            .update((self.x_fn)(data), (self.y_fn)(data));
+        collector.push(&(self.out_fn)(r, data));
+    }
+}
+
+/// Rolling pair-wise Pearson correlations between `N` series over the last `window` samples.
+///
+/// Means and co-moments are updated incrementally (Welford) as samples enter and leave the
+/// window, so an update costs `O(N^2)` regardless of the window size.
+pub struct CorrelationMatrix<const N: usize> {
+    window: usize,
+    samples: VecDeque<[f64; N]>,
+    mean: [f64; N],
+    /// Sums of the products of deviations from the mean; the diagonal holds the variances.
+    comoment: [[f64; N]; N],
+}
+
+impl<const N: usize> CorrelationMatrix<N> {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window must not be zero");
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window),
+            mean: [0.0; N],
+            comoment: [[0.0; N]; N],
+        }
+    }
+
+    /// Adds a sample and returns the correlation matrix of the samples in the window.
+    ///
+    /// Entry `[i][j]` is the correlation between series `i` and `j`. Entries involving a
+    /// constant series, and all entries while fewer than two samples were seen, are `NaN`.
+    pub fn update(&mut self, values: [f64; N]) -> [[f64; N]; N] {
+        if self.samples.len() == self.window {
+            let oldest = self.samples.pop_front().unwrap();
+            self.remove(&oldest);
+        }
+        self.samples.push_back(values);
+        self.add(&values);
+        self.correlations()
+    }
+
+    fn add(&mut self, values: &[f64; N]) {
+        let n = self.samples.len() as f64;
+        let mut before = [0.0; N];
+        for i in 0..N {
+            before[i] = values[i] - self.mean[i];
+            self.mean[i] += before[i] / n;
+        }
+        for (row, b) in self.comoment.iter_mut().zip(before) {
+            for ((c, v), m) in row.iter_mut().zip(values).zip(self.mean) {
+                *c += b * (v - m);
+            }
+        }
+    }
+
+    fn remove(&mut self, values: &[f64; N]) {
+        let n = self.samples.len() as f64;
+        if n == 0.0 {
+            self.mean = [0.0; N];
+            self.comoment = [[0.0; N]; N];
+            return;
+        }
+        let mut after = [0.0; N];
+        for i in 0..N {
+            after[i] = values[i] - self.mean[i];
+            self.mean[i] -= after[i] / n;
+        }
+        for ((row, v), m) in self.comoment.iter_mut().zip(values).zip(self.mean) {
+            for (c, a) in row.iter_mut().zip(after) {
+                *c -= (v - m) * a;
+            }
+        }
+    }
+
+    fn correlations(&self) -> [[f64; N]; N] {
+        let mut out = [[f64::NAN; N]; N];
+        if self.samples.len() < 2 {
+            return out;
+        }
+        for (i, row) in out.iter_mut().enumerate() {
+            for (j, r) in row.iter_mut().enumerate() {
+                let denom = (self.comoment[i][i] * self.comoment[j][j]).sqrt();
+                if denom > 0.0 {
+                    *r = (self.comoment[i][j] / denom).clamp(-1.0, 1.0);
+                }
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Streaming quantile estimates over an unbounded range of values (t-digest).
+///
+/// Values are merged into centroids that are small near the extremes and large around
+/// the median, so tail quantiles stay accurate while memory is bounded by about
+/// `3 * compression` centroids regardless of the number of values.
+#[derive(Debug, Clone)]
+pub struct QuantileTracker {
+    compression: f64,
+    /// Sorted by mean.
+    centroids: Vec<Centroid>,
+    count: f64,
+    min: f64,
+    max: f64,
+    /// Whether the last compression merged from the largest centroid down.
+    reverse: bool,
+}
+
+impl QuantileTracker {
+    /// Creates a tracker; higher `compression` is more accurate and uses more memory.
+    pub fn new(compression: f64) -> Self {
+        assert!(compression >= 1.0, "compression must be at least 1");
+        Self {
+            compression,
+            centroids: Vec::with_capacity(3 * compression as usize + 1),
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            reverse: false,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) {
+        let at = self.centroids.partition_point(|c| c.mean <= value);
+        self.centroids.insert(
+            at,
+            Centroid {
+                mean: value,
+                weight: 1.0,
+            },
+        );
+        self.count += 1.0;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.centroids.len() as f64 > 3.0 * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Returns the estimated value at quantile `q`, between 0 and 1.
+    ///
+    /// Returns `NaN` if no value was added.
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.centroids.is_empty() {
+            return f64::NAN;
+        }
+        let target = q.clamp(0.0, 1.0) * self.count;
+        // Interpolate between the centers of neighbouring centroids, with the minimum at
+        // rank 0 and the maximum at the last rank.
+        let (mut prev_rank, mut prev_value) = (0.0, self.min);
+        let mut cumulative = 0.0;
+        for c in &self.centroids {
+            let rank = cumulative + c.weight / 2.0;
+            if target < rank {
+                return interpolate(prev_rank, prev_value, rank, c.mean, target);
+            }
+            (prev_rank, prev_value) = (rank, c.mean);
+            cumulative += c.weight;
+        }
+        interpolate(prev_rank, prev_value, self.count, self.max, target)
+    }
+
+    /// Adds the values tracked by `other`.
+    pub fn merge(&mut self, other: &QuantileTracker) {
+        let mut centroids = Vec::with_capacity(self.centroids.len() + other.centroids.len());
+        let (mut a, mut b) = (
+            self.centroids.iter().peekable(),
+            other.centroids.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            if x.mean <= y.mean {
+                centroids.push(*a.next().unwrap());
+            } else {
+                centroids.push(*b.next().unwrap());
+            }
+        }
+        centroids.extend(a.chain(b));
+        self.centroids = centroids;
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress();
+    }
+
+    /// Returns the number of values added.
+    pub fn count(&self) -> u64 {
+        self.count as u64
+    }
+
+    /// Merges neighbouring centroids while the quantiles they span stay within one unit of
+    /// the scale `k(q) = compression / (2 * PI) * asin(2 * q - 1)`, which leaves at most
+    /// about `compression` centroids.
+    fn compress(&mut self) {
+        // Alternate the merge direction so neither tail collects the fuller centroids
+        self.reverse = !self.reverse;
+        if self.reverse {
+            self.centroids.reverse();
+        }
+        let Some((&first, rest)) = self.centroids.split_first() else {
+            return;
+        };
+        let mut merged = Vec::with_capacity(self.centroids.capacity());
+        let mut current = first;
+        let mut cumulative = 0.0;
+        for next in rest {
+            let weight = current.weight + next.weight;
+            let k_start = self.scale(cumulative / self.count);
+            let k_end = self.scale((cumulative + weight) / self.count);
+            if k_end - k_start <= 1.0 {
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                cumulative += current.weight;
+                merged.push(current);
+                current = *next;
+            }
+        }
+        merged.push(current);
+        if self.reverse {
+            merged.reverse();
+        }
+        self.centroids = merged;
+    }
+
+    fn scale(&self, q: f64) -> f64 {
+        self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin()
+    }
+}
+
+impl Default for QuantileTracker {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+fn interpolate(x0: f64, y0: f64, x1: f64, y1: f64, x: f64) -> f64 {
+    if x1 <= x0 {
+        return y1;
+    }
+    y0 + (y1 - y0) * ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)
+}
+
+/// Passes the running estimate of quantile `q` of `value_fn` to `output_fn` together with
+/// each item, see `QuantileTracker`.
+///
+/// The estimate is recomputed for every item by walking all centroids, so each item costs
+/// `O(compression)`, up to about 300 centroid visits with the default compression.
+pub fn quantile_stage<T: Pod + Send>(
+    value_fn: impl Fn(&T) -> f64 + Send,
+    q: f64,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> impl Stage<T, T> + Send {
+    QuantileStage {
+        tracker: QuantileTracker::default(),
+        value_fn,
+        q,
+        output_fn,
+        _phantom: PhantomData,
+    }
+}
+
+struct QuantileStage<T, V, O> {
+    tracker: QuantileTracker,
+    value_fn: V,
+    q: f64,
+    output_fn: O,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, V, O> Stage<T, T> for QuantileStage<T, V, O>
+where
+    T: Pod + Send,
+    V: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        self.tracker.update((self.value_fn)(data));
+        collector.push(&(self.output_fn)(self.tracker.quantile(self.q), data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X: [f64; 8] = [1.0, 2.0, 4.0, 3.0, 7.0, 6.0, 9.0, 8.0];
+    const Y: [f64; 8] = [2.0, 1.5, 3.5, 4.0, 6.5, 5.0, 9.5, 7.0];
+    const Z: [f64; 8] = [5.0, 3.0, 4.0, 1.0, 2.0, 0.5, -1.0, 0.0];
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    // Reference values from Python's statistics.correlation, which computes the same
+    // coefficient as scipy.stats.pearsonr.
+    #[test]
+    fn test_correlation_full_window() {
+        let mut corr = Correlation::new(8);
+        let mut r = f64::NAN;
+        for (x, y) in X.iter().zip(Y) {
+            r = corr.update(*x, y);
+        }
+        assert_close(r, 0.9592858386473346);
+    }
+
+    #[test]
+    fn test_correlation_rolling_window() {
+        let expected = [
+            0.7592566023652966,
+            0.9379030918551611,
+            0.8970852271450606,
+            0.9313643827882673,
+            0.9660917830792959,
+        ];
+        let mut corr = Correlation::new(4);
+        let out: Vec<f64> = X.iter().zip(Y).map(|(x, y)| corr.update(*x, y)).collect();
+        assert!(out[0].is_nan());
+        for (r, e) in out[3..].iter().zip(expected) {
+            assert_close(*r, e);
+        }
+    }
+
+    #[test]
+    fn test_correlation_constant_series() {
+        let mut corr = Correlation::new(4);
+        for x in X {
+            assert!(corr.update(x, 1.0).is_nan());
+        }
+    }
+
+    #[test]
+    fn test_correlation_as_stage() {
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+        struct Prices {
+            a: f64,
+            b: f64,
+            corr: f64,
+        }
+
+        let mut stage = Correlation::new(8).as_stage(
+            |p: &Prices| p.a,
+            |p: &Prices| p.b,
+            |corr, p: &Prices| Prices { corr, ..*p },
+        );
+        let mut last = None;
+        for (a, b) in X.iter().zip(Y) {
+            let p = Prices {
+                a: *a,
+                b,
+                corr: 0.0,
+            };
+            stage.process(&p, &mut |p: &Prices| last = Some(p.corr));
+        }
+        assert_close(last.unwrap(), 0.9592858386473346);
+    }
+
+    #[test]
+    fn test_correlation_matrix() {
+        let mut matrix = CorrelationMatrix::<3>::new(4);
+        let mut out = [[f64::NAN; 3]; 3];
+        for ((x, y), z) in X.iter().zip(Y).zip(Z) {
+            out = matrix.update([*x, y, z]);
+        }
+        assert_close(out[0][1], 0.9660917830792959);
+        assert_close(out[0][2], -0.6713171133426189);
+        assert_close(out[1][2], -0.6414269805898185);
+        for (i, row) in out.iter().enumerate() {
+            assert_close(row[i], 1.0);
+            for (j, r) in row.iter().enumerate() {
+                assert_close(*r, out[j][i]);
+            }
+        }
+    }
+
+    /// Exponentially distributed values from a xorshift generator.
+    fn exponential_values(n: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let u = (state >> 11) as f64 / (1u64 << 53) as f64;
+                -(1.0 - u).ln() * 1000.0
+            })
+            .collect()
+    }
+
+    /// Asserts that `estimate` lies within `tolerance` of quantile `q` in `sorted` by rank.
+    fn assert_rank_close(sorted: &[f64], q: f64, estimate: f64, tolerance: f64) {
+        let rank = sorted.partition_point(|v| *v < estimate) as f64 / sorted.len() as f64;
+        assert!(
+            (rank - q).abs() <= tolerance,
+            "q={} estimate={} has rank {}",
+            q,
+            estimate,
+            rank
+        );
+    }
+
+    const QUANTILES: [f64; 9] = [0.001, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 0.999];
+
+    #[test]
+    fn test_quantile_tracker_matches_sorted_quantiles() {
+        let values = exponential_values(50_000, 42);
+        let mut tracker = QuantileTracker::default();
+        assert!(tracker.quantile(0.5).is_nan());
+        for v in &values {
+            tracker.update(*v);
+        }
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+
+        assert_eq!(tracker.count(), 50_000);
+        assert!(tracker.centroids.len() <= 300);
+        let mut compressed = tracker.clone();
+        compressed.compress();
+        assert!(compressed.centroids.len() <= 100);
+        assert_eq!(tracker.quantile(0.0), sorted[0]);
+        assert_eq!(tracker.quantile(1.0), sorted[sorted.len() - 1]);
+        for q in QUANTILES {
+            assert_rank_close(&sorted, q, tracker.quantile(q), 0.005);
+        }
+    }
+
+    #[test]
+    fn test_quantile_tracker_merge() {
+        let values = exponential_values(20_000, 7);
+        let (mut left, mut right) = (QuantileTracker::default(), QuantileTracker::default());
+        for v in &values[..10_000] {
+            left.update(*v);
+        }
+        for v in &values[10_000..] {
+            right.update(*v + 500.0);
+        }
+        left.merge(&right);
+
+        let mut sorted: Vec<f64> = values[..10_000]
+            .iter()
+            .copied()
+            .chain(values[10_000..].iter().map(|v| v + 500.0))
+            .collect();
+        sorted.sort_by(f64::total_cmp);
+
+        assert_eq!(left.count(), 20_000);
+        for q in QUANTILES {
+            assert_rank_close(&sorted, q, left.quantile(q), 0.005);
+        }
+    }
+
+    #[test]
+    fn test_quantile_stage() {
+        let values = exponential_values(10_000, 3);
+        let mut stage = quantile_stage(|x: &f64| *x, 0.5, |median, _| median);
+        let mut last = f64::NAN;
+        for v in &values {
+            stage.process(v, &mut |m: &f64| last = *m);
+        }
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert_rank_close(&sorted, 0.5, last, 0.005);
+    }
+}
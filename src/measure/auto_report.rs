@@ -0,0 +1,117 @@
+//! A `LatencyMeasurer` that logs its statistics periodically from a background thread.
+use crate::logging::logger;
+use crate::measure::{LatencyMeasurer, LatencyStats};
+use spdlog::info;
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Wraps a `LatencyMeasurer` and logs `format_stats` every interval.
+///
+/// The background thread is stopped when this is dropped.
+pub struct AutoReportingLatencyMeasurer {
+    measurer: Arc<Mutex<LatencyMeasurer>>,
+    // Dropping the sender wakes up and stops the reporting thread
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AutoReportingLatencyMeasurer {
+    pub(crate) fn new(measurer: LatencyMeasurer, interval: Duration, label: &'static str) -> Self {
+        Self::with_reporter(
+            measurer,
+            interval,
+            move |stats| info!(logger: logger(), "[{}] Latency: {}", label, stats, kv: { label = label }),
+        )
+    }
+
+    fn with_reporter(
+        measurer: LatencyMeasurer,
+        interval: Duration,
+        report: impl Fn(&str) + Send + 'static,
+    ) -> Self {
+        let measurer = Arc::new(Mutex::new(measurer));
+        let (stop, stopped) = channel::<()>();
+        let handle = {
+            let measurer = measurer.clone();
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let stats = measurer.lock().unwrap().format_stats();
+                    report(&stats);
+                }
+            })
+        };
+        Self {
+            measurer,
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    pub fn measure(&self, duration: Duration) {
+        self.measurer.lock().unwrap().measure(duration);
+    }
+
+    pub fn step_measure(&self) {
+        self.measurer.lock().unwrap().step_measure();
+    }
+
+    pub fn reset(&self) {
+        self.measurer.lock().unwrap().reset();
+    }
+
+    pub fn get_stats(&self) -> LatencyStats {
+        self.measurer.lock().unwrap().get_stats()
+    }
+
+    pub fn format_stats(&self) -> String {
+        self.measurer.lock().unwrap().format_stats()
+    }
+}
+
+impl Drop for AutoReportingLatencyMeasurer {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    #[test]
+    fn test_auto_report_logs_periodically_and_stops_on_drop() {
+        let interval = Duration::from_millis(20);
+        let reports = Arc::new(AtomicUsize::new(0));
+        let measurer = {
+            let reports = reports.clone();
+            AutoReportingLatencyMeasurer::with_reporter(
+                LatencyMeasurer::new(1),
+                interval,
+                move |_| {
+                    reports.fetch_add(1, Ordering::Relaxed);
+                },
+            )
+        };
+        measurer.measure(Duration::from_micros(5));
+
+        // Generous deadline, a loaded machine may delay the reporting thread
+        let start = Instant::now();
+        while reports.load(Ordering::Relaxed) == 0 {
+            assert!(start.elapsed() < Duration::from_secs(5), "no report");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(measurer.get_stats().count, 1);
+
+        drop(measurer);
+        let after_drop = reports.load(Ordering::Relaxed);
+        thread::sleep(interval * 3);
+        assert_eq!(reports.load(Ordering::Relaxed), after_drop);
+    }
+}
@@ -0,0 +1,15 @@
+use crate::measure::LatencyStats;
+use std::time::Duration;
+
+/// The result of `RodaEngine::bench_worker`.
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    /// Latency of the measured iterations.
+    pub latency: LatencyStats,
+    /// Number of measured iterations.
+    pub iterations: u64,
+    /// Total time spent in the measured iterations.
+    pub elapsed: Duration,
+    /// Measured iterations per second, or 0 if no time was measured.
+    pub throughput: f64,
+}
@@ -1,8 +1,13 @@
+use crate::measure::AutoReportingLatencyMeasurer;
 use hdrhistogram::Histogram;
 use std::time::{Duration, Instant};
 
 /// Statistics for latency measurements.
-#[derive(Debug, Clone, Default)]
+///
+/// With the `serde` feature it can be serialized, using the field names of
+/// `LatencyMeasurer::format_stats` with plain nanosecond values.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LatencyStats {
     /// Total number of samples.
     pub count: u64,
@@ -24,6 +29,47 @@ pub struct LatencyStats {
     pub p9999: u64,
 }
 
+impl LatencyStats {
+    /// Returns the statistics of `measurer` together with the `(quantile, latency)` pairs of
+    /// the given quantiles (between 0 and 1), for example `&[0.95, 0.995]`.
+    ///
+    /// The pairs are empty if nothing was measured.
+    pub fn from_measurer_with_percentiles(
+        measurer: &LatencyMeasurer,
+        percentiles: &[f64],
+    ) -> (Self, Vec<(f64, u64)>) {
+        let stats = measurer.get_stats();
+        if stats.count == 0 {
+            return (stats, vec![]);
+        }
+        let pairs = percentiles
+            .iter()
+            .zip(measurer.iter_quantiles(percentiles))
+            .map(|(q, value)| (*q, value))
+            .collect();
+        (stats, pairs)
+    }
+
+    /// Formats the statistics like `LatencyMeasurer::format_stats`.
+    pub fn format(&self) -> String {
+        if self.count == 0 {
+            return "No stats collected yet".into();
+        }
+
+        format!(
+            "\tmin={},\tmax={},\tmean={},\tp50={},\tp90={},\tp99={},\tp999={},\tp9999={}",
+            LatencyMeasurer::format_duration(self.min as f64),
+            LatencyMeasurer::format_duration(self.max as f64),
+            LatencyMeasurer::format_duration(self.mean),
+            LatencyMeasurer::format_duration(self.p50 as f64),
+            LatencyMeasurer::format_duration(self.p90 as f64),
+            LatencyMeasurer::format_duration(self.p99 as f64),
+            LatencyMeasurer::format_duration(self.p999 as f64),
+            LatencyMeasurer::format_duration(self.p9999 as f64),
+        )
+    }
+}
+
 pub struct LatencyMeasurerGuard<'a> {
     measurer: &'a mut LatencyMeasurer,
     start: Option<Instant>,
@@ -63,6 +109,15 @@ impl LatencyMeasurer {
         }
     }
 
+    /// Moves the measurer to a background thread that logs `format_stats` every `interval`.
+    pub fn with_auto_report(
+        self,
+        interval: Duration,
+        label: &'static str,
+    ) -> AutoReportingLatencyMeasurer {
+        AutoReportingLatencyMeasurer::new(self, interval, label)
+    }
+
     pub fn measure(&mut self, duration: Duration) {
         self.step += 1;
         if !self.step.is_multiple_of(self.sample_rate) {
@@ -128,23 +183,31 @@ impl LatencyMeasurer {
         }
     }
 
+    /// Returns the latency in nanoseconds at quantile `q`, between 0 and 1 (e.g. 0.95 for p95).
+    pub fn percentile(&self, q: f64) -> u64 {
+        self.histogram.value_at_quantile(q)
+    }
+
+    /// Returns the latencies in nanoseconds at each of the given quantiles.
+    pub fn iter_quantiles(&self, quantiles: &[f64]) -> Vec<u64> {
+        quantiles.iter().map(|q| self.percentile(*q)).collect()
+    }
+
     pub fn format_stats(&self) -> String {
-        let stats = self.get_stats();
-        if stats.count == 0 {
-            return "No stats collected yet".into();
-        }
+        self.get_stats().format()
+    }
 
-        format!(
-            "\tmin={},\tmax={},\tmean={},\tp50={},\tp90={},\tp99={},\tp999={},\tp9999={}",
-            Self::format_duration(stats.min as f64),
-            Self::format_duration(stats.max as f64),
-            Self::format_duration(stats.mean),
-            Self::format_duration(stats.p50 as f64),
-            Self::format_duration(stats.p90 as f64),
-            Self::format_duration(stats.p99 as f64),
-            Self::format_duration(stats.p999 as f64),
-            Self::format_duration(stats.p9999 as f64),
-        )
+    /// Serializes the current statistics as JSON.
+    #[cfg(feature = "serde")]
+    pub fn export_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.get_stats())
+    }
+
+    /// Parses statistics exported with `export_json`, e.g. a baseline to compare the
+    /// current statistics against.
+    #[cfg(feature = "serde")]
+    pub fn import_baseline(json: &str) -> Result<LatencyStats, serde_json::Error> {
+        serde_json::from_str(json)
     }
 
     fn format_duration(nanos: f64) -> String {
@@ -167,3 +230,79 @@ impl LatencyMeasurer {
         duration.as_nanos() as u64 > stats.p999
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_matches_histogram() {
+        let mut measurer = LatencyMeasurer::new(1);
+        for i in 1..=1000u64 {
+            measurer.measure(Duration::from_nanos(i * 100));
+        }
+
+        for q in [0.0, 0.5, 0.95, 0.995, 1.0] {
+            assert_eq!(
+                measurer.percentile(q),
+                measurer.histogram.value_at_quantile(q)
+            );
+        }
+        assert_eq!(
+            measurer.iter_quantiles(&[0.95, 0.995]),
+            vec![
+                measurer.histogram.value_at_quantile(0.95),
+                measurer.histogram.value_at_quantile(0.995)
+            ]
+        );
+
+        let (stats, percentiles) =
+            LatencyStats::from_measurer_with_percentiles(&measurer, &[0.95, 0.995]);
+        assert_eq!(stats, measurer.get_stats());
+        assert_eq!(
+            percentiles,
+            vec![
+                (0.95, measurer.histogram.value_at_quantile(0.95)),
+                (0.995, measurer.histogram.value_at_quantile(0.995))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percentiles_empty_measurer() {
+        let measurer = LatencyMeasurer::new(1);
+        let (stats, percentiles) = LatencyStats::from_measurer_with_percentiles(&measurer, &[0.95]);
+        assert_eq!(stats.count, 0);
+        assert!(percentiles.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_latency_stats_json_round_trip() {
+        let stats = LatencyStats {
+            count: 1000,
+            min: 100,
+            max: 100_000,
+            mean: 50_050.5,
+            p50: 50_000,
+            p90: 90_000,
+            p99: 99_000,
+            p999: 99_900,
+            p9999: 100_000,
+        };
+        let json = serde_json::to_string(&stats).unwrap();
+        assert!(json.contains("\"p999\":99900"));
+        assert_eq!(LatencyMeasurer::import_baseline(&json).unwrap(), stats);
+
+        let mut measurer = LatencyMeasurer::new(1);
+        for i in 1..=1000u64 {
+            measurer.measure(Duration::from_nanos(i * 100));
+        }
+        let baseline = LatencyMeasurer::import_baseline(&measurer.export_json().unwrap()).unwrap();
+        let current = measurer.get_stats();
+        assert_eq!(baseline.count, current.count);
+        assert_eq!(baseline.p99, current.p99);
+        assert!((baseline.mean - current.mean).abs() < 1e-6);
+        assert!(LatencyMeasurer::import_baseline("{}").is_err());
+    }
+}
@@ -1,5 +1,9 @@
+mod auto_report;
+mod benchmark;
 mod e2e_latency_measurer;
 pub mod latency_measurer;
 
+pub use auto_report::AutoReportingLatencyMeasurer;
+pub use benchmark::BenchmarkResult;
 pub use e2e_latency_measurer::E2ELatencyMeasurer;
 pub use latency_measurer::{LatencyMeasurer, LatencyStats};
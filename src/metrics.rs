@@ -0,0 +1,81 @@
+//! Prometheus text exposition of store metrics.
+use crate::engine::RodaEngine;
+use crate::journal_store::JournalStore;
+use bytemuck::Pod;
+use std::fmt::Write;
+
+fn write_family(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+impl<State: Pod + Send> JournalStore<State> {
+    /// Formats the number of appended items as a Prometheus counter called `name`.
+    pub fn to_prometheus_counter(&self, name: &str) -> String {
+        let mut out = String::new();
+        write_family(
+            &mut out,
+            name,
+            "counter",
+            "Number of items appended to the store.",
+        );
+        writeln!(out, "{} {}", name, self.size()).unwrap();
+        out
+    }
+}
+
+/// Formats the metrics of all stores registered with `engine` as a Prometheus text document.
+///
+/// Each store is labelled with its registered name.
+pub fn collect_all_metrics(engine: &RodaEngine) -> String {
+    let stores = engine.list_stores();
+    let mut out = String::new();
+
+    write_family(
+        &mut out,
+        "roda_store_items_total",
+        "counter",
+        "Number of items appended to the store.",
+    );
+    for store in &stores {
+        writeln!(
+            out,
+            "roda_store_items_total{{store=\"{}\"}} {}",
+            store.name, store.count
+        )
+        .unwrap();
+    }
+
+    write_family(
+        &mut out,
+        "roda_store_capacity",
+        "gauge",
+        "Maximum number of items the store can hold.",
+    );
+    for store in &stores {
+        writeln!(
+            out,
+            "roda_store_capacity{{store=\"{}\"}} {}",
+            store.name, store.capacity
+        )
+        .unwrap();
+    }
+
+    write_family(
+        &mut out,
+        "roda_store_utilisation",
+        "gauge",
+        "Fraction of the store capacity in use.",
+    );
+    for store in &stores {
+        writeln!(
+            out,
+            "roda_store_utilisation{{store=\"{}\"}} {}",
+            store.name,
+            store.utilisation()
+        )
+        .unwrap();
+    }
+
+    out
+}
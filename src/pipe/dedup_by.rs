@@ -1,7 +1,11 @@
+use crate::pipe::timestamp_nanos;
 use crate::stage::{OutputCollector, Stage};
 use bytemuck::Pod;
-use std::collections::HashMap;
+use fxhash::{FxHashMap, FxHashSet};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::time::Duration;
 
 /// Only emits the event if the value associated with the key has changed.
 pub struct DedupBy<K, T, F> {
@@ -58,6 +62,248 @@ where
     DedupBy::new(key_fn)
 }
 
+/// Drops every item whose key has been seen before.
+///
+/// When bounded, only the most recent `capacity` keys are remembered; older keys are
+/// evicted in insertion order and can pass again.
+pub struct DedupSeen<K, T, F> {
+    key_fn: F,
+    seen: FxHashSet<K>,
+    order: VecDeque<K>,
+    capacity: Option<usize>,
+    _phantom: PhantomData<T>,
+}
+
+impl<K, T, F> DedupSeen<K, T, F>
+where
+    K: Hash + Eq + Clone,
+    T: Pod,
+    F: Fn(&T) -> K,
+{
+    pub fn new(key_fn: F, capacity: Option<usize>) -> Self {
+        assert!(capacity != Some(0), "capacity must be greater than 0");
+        Self {
+            key_fn,
+            seen: FxHashSet::default(),
+            order: VecDeque::with_capacity(capacity.unwrap_or(0)),
+            capacity,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, T, F> Stage<T, T> for DedupSeen<K, T, F>
+where
+    K: Hash + Eq + Clone + Send,
+    T: Pod + Send,
+    F: Fn(&T) -> K + Send,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, curr: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let key = (self.key_fn)(curr);
+        if self.seen.contains(&key) {
+            return;
+        }
+
+        if let Some(capacity) = self.capacity {
+            if self.order.len() == capacity
+                && let Some(oldest) = self.order.pop_front()
+            {
+                self.seen.remove(&oldest);
+            }
+            self.order.push_back(key.clone());
+        }
+        self.seen.insert(key);
+        collector.push(curr);
+    }
+}
+
+/// Compares and hashes a `Pod` value by its byte representation.
+#[derive(Clone, Copy)]
+pub struct PodBytes<T: Pod>(T);
+
+impl<T: Pod> PartialEq for PodBytes<T> {
+    fn eq(&self, other: &Self) -> bool {
+        bytemuck::bytes_of(&self.0) == bytemuck::bytes_of(&other.0)
+    }
+}
+
+impl<T: Pod> Eq for PodBytes<T> {}
+
+impl<T: Pod> Hash for PodBytes<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(bytemuck::bytes_of(&self.0));
+    }
+}
+
+/// Drops items equal to any item seen before.
+pub fn dedup_by_value<T: Pod + Send + Eq + Hash>() -> DedupSeen<T, T, impl Fn(&T) -> T + Send> {
+    DedupSeen::new(|x: &T| *x, None)
+}
+
+/// Like `dedup_by_value`, but only remembers the last `capacity` distinct items.
+pub fn dedup_by_value_bounded<T: Pod + Send + Eq + Hash>(
+    capacity: usize,
+) -> DedupSeen<T, T, impl Fn(&T) -> T + Send> {
+    DedupSeen::new(|x: &T| *x, Some(capacity))
+}
+
+/// Drops items whose bytes are equal to those of any item seen before.
+///
+/// Useful for `Pod` types that do not implement `Hash` and `Eq`, such as structs with floats.
+pub fn dedup_by_bytes<T: Pod + Send>()
+-> DedupSeen<PodBytes<T>, T, impl Fn(&T) -> PodBytes<T> + Send> {
+    DedupSeen::new(|x: &T| PodBytes(*x), None)
+}
+
+/// Like `dedup_by_bytes`, but only remembers the last `capacity` distinct items.
+pub fn dedup_by_bytes_bounded<T: Pod + Send>(
+    capacity: usize,
+) -> DedupSeen<PodBytes<T>, T, impl Fn(&T) -> PodBytes<T> + Send> {
+    DedupSeen::new(|x: &T| PodBytes(*x), Some(capacity))
+}
+
+/// Drops an item if an item with the same key was emitted within the last `window_items`
+/// items, counting dropped items too.
+pub struct DedupWindow<K, T, F> {
+    key_fn: F,
+    emitted: FxHashSet<K>,
+    /// The keys of the last `window_items` items, `None` for dropped items.
+    window: VecDeque<Option<K>>,
+    window_items: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<K, T, F> DedupWindow<K, T, F>
+where
+    K: Hash + Eq + Clone,
+    T: Pod,
+    F: Fn(&T) -> K,
+{
+    pub fn new(window_items: usize, key_fn: F) -> Self {
+        assert!(window_items > 0, "window_items must be greater than 0");
+        Self {
+            key_fn,
+            emitted: FxHashSet::default(),
+            window: VecDeque::with_capacity(window_items + 1),
+            window_items,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, T, F> Stage<T, T> for DedupWindow<K, T, F>
+where
+    K: Hash + Eq + Clone + Send,
+    T: Pod + Send,
+    F: Fn(&T) -> K + Send,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, curr: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let key = (self.key_fn)(curr);
+        if self.emitted.contains(&key) {
+            self.window.push_back(None);
+        } else {
+            self.emitted.insert(key.clone());
+            self.window.push_back(Some(key));
+            collector.push(curr);
+        }
+
+        if self.window.len() > self.window_items
+            && let Some(Some(oldest)) = self.window.pop_front()
+        {
+            self.emitted.remove(&oldest);
+        }
+    }
+}
+
+/// Drops an item if an item with the same key was emitted less than `window` ago,
+/// according to `timestamp_nanos`.
+pub struct DedupTimeWindow<K, T, F> {
+    key_fn: F,
+    window_nanos: u64,
+    emitted: FxHashMap<K, u64>,
+    /// Emission times and keys, oldest first.
+    order: VecDeque<(u64, K)>,
+    _phantom: PhantomData<T>,
+}
+
+impl<K, T, F> DedupTimeWindow<K, T, F>
+where
+    K: Hash + Eq + Clone,
+    T: Pod,
+    F: Fn(&T) -> K,
+{
+    pub fn new(window: Duration, key_fn: F) -> Self {
+        Self {
+            key_fn,
+            window_nanos: window.as_nanos() as u64,
+            emitted: FxHashMap::default(),
+            order: VecDeque::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, T, F> Stage<T, T> for DedupTimeWindow<K, T, F>
+where
+    K: Hash + Eq + Clone + Send,
+    T: Pod + Send,
+    F: Fn(&T) -> K + Send,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, curr: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let now = timestamp_nanos();
+        while let Some((emitted_at, _)) = self.order.front()
+            && now.saturating_sub(*emitted_at) >= self.window_nanos
+        {
+            let (_, key) = self.order.pop_front().unwrap();
+            self.emitted.remove(&key);
+        }
+
+        let key = (self.key_fn)(curr);
+        if self.emitted.contains_key(&key) {
+            return;
+        }
+        self.emitted.insert(key.clone(), now);
+        self.order.push_back((now, key));
+        collector.push(curr);
+    }
+}
+
+/// Drops items whose key was already emitted within the last `window_items` items.
+pub fn dedup_by_window<K, T>(
+    window_items: usize,
+    key_fn: impl Fn(&T) -> K + Send,
+) -> DedupWindow<K, T, impl Fn(&T) -> K + Send>
+where
+    K: Hash + Eq + Clone,
+    T: Pod,
+{
+    DedupWindow::new(window_items, key_fn)
+}
+
+/// Drops items whose key was already emitted less than `window` ago.
+pub fn dedup_by_time_window<K, T>(
+    window: Duration,
+    key_fn: impl Fn(&T) -> K + Send,
+) -> DedupTimeWindow<K, T, impl Fn(&T) -> K + Send>
+where
+    K: Hash + Eq + Clone,
+    T: Pod,
+{
+    DedupTimeWindow::new(window, key_fn)
+}
+
 #[cfg(test)]
 mod dedup_tests {
     use super::*;
@@ -74,4 +320,115 @@ mod dedup_tests {
 
         assert_eq!(out, vec![10, 20, 10]);
     }
+
+    #[test]
+    fn test_dedup_by_value() {
+        let mut pipe = dedup_by_value::<u64>();
+        let mut out = Vec::new();
+
+        for x in [1u64, 2, 1, 3, 2, 3, 4] {
+            pipe.process(&x, &mut |x: &u64| out.push(*x));
+        }
+
+        assert_eq!(out, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_dedup_by_value_bounded_evicts_oldest() {
+        let mut pipe = dedup_by_value_bounded::<u64>(2);
+        let mut out = Vec::new();
+
+        // 1 is evicted when 3 arrives, so it passes again
+        for x in [1u64, 2, 2, 3, 1, 3] {
+            pipe.process(&x, &mut |x: &u64| out.push(*x));
+        }
+
+        assert_eq!(out, vec![1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_dedup_by_bytes() {
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+        struct Quote {
+            price: f64,
+            size: u64,
+        }
+
+        let mut pipe = dedup_by_bytes::<Quote>();
+        let mut out = Vec::new();
+
+        for (price, size) in [(1.5, 10), (1.5, 10), (1.5, 20), (2.0, 10), (1.5, 20)] {
+            pipe.process(&Quote { price, size }, &mut |q: &Quote| out.push(*q));
+        }
+
+        assert_eq!(
+            out,
+            vec![
+                Quote {
+                    price: 1.5,
+                    size: 10
+                },
+                Quote {
+                    price: 1.5,
+                    size: 20
+                },
+                Quote {
+                    price: 2.0,
+                    size: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_by_bytes_bounded() {
+        let mut pipe = dedup_by_bytes_bounded::<u32>(1);
+        let mut out = Vec::new();
+
+        for x in [7u32, 7, 8, 7, 7] {
+            pipe.process(&x, &mut |x: &u32| out.push(*x));
+        }
+
+        assert_eq!(out, vec![7, 8, 7]);
+    }
+
+    #[test]
+    fn test_dedup_by_window_re_emits_after_window() {
+        let mut pipe = dedup_by_window(3, |x: &u32| *x / 10);
+        let mut out = Vec::new();
+
+        // 11 and 12 are within 3 items of 10; 13 is not, as 10 has left the window
+        for x in [10u32, 20, 11, 12, 13, 21, 14] {
+            pipe.process(&x, &mut |x: &u32| out.push(*x));
+        }
+
+        assert_eq!(out, vec![10, 20, 13, 21]);
+    }
+
+    #[test]
+    fn test_dedup_by_time_window_re_emits_after_window() {
+        use crate::testing::{MockClock, with_mock_clock};
+
+        let clock = MockClock::new(0);
+        let mut pipe = dedup_by_time_window(Duration::from_nanos(100), |x: &u32| *x / 10);
+        let mut out = Vec::new();
+
+        with_mock_clock(clock.clone(), || {
+            for (at, x) in [
+                (0, 10u32),
+                (50, 11),
+                (60, 20),
+                (99, 12),
+                (100, 13),
+                (150, 21),
+                (160, 22),
+            ] {
+                clock.set(at);
+                pipe.process(&x, &mut |x: &u32| out.push(*x));
+            }
+        });
+
+        assert_eq!(out, vec![10, 20, 13, 22]);
+    }
 }
@@ -0,0 +1,90 @@
+use crate::ring_buffer::RingBuffer;
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Emits the difference between the current value and the value `K` items earlier.
+///
+/// `K = 1` is the first discrete difference. Nothing is emitted for the first `K` items,
+/// as there is no earlier value to compare with yet.
+pub struct Diff<T, const K: usize, VF, OF> {
+    value_fn: VF,
+    output_fn: OF,
+    history: RingBuffer<f64, K>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const K: usize, VF, OF> Diff<T, K, VF, OF>
+where
+    T: Pod + Send,
+    VF: Fn(&T) -> f64,
+    OF: Fn(f64, &T) -> T,
+{
+    pub fn new(value_fn: VF, output_fn: OF) -> Self {
+        Self {
+            value_fn,
+            output_fn,
+            history: RingBuffer::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, const K: usize, VF, OF> Stage<T, T> for Diff<T, K, VF, OF>
+where
+    T: Pod + Send,
+    VF: Fn(&T) -> f64,
+    OF: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let value = (self.value_fn)(data);
+        let oldest = if self.history.is_full() {
+            self.history.pop()
+        } else {
+            None
+        };
+        self.history.push(value);
+        if let Some(oldest) = oldest {
+            collector.push(&(self.output_fn)(value - oldest, data));
+        }
+    }
+}
+
+/// Replaces each item with `output_fn(value - value_k_items_ago, item)`.
+pub fn diff<T: Pod + Send, const K: usize>(
+    value_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> Diff<T, K, impl Fn(&T) -> f64 + Send, impl Fn(f64, &T) -> T + Send> {
+    Diff::new(value_fn, output_fn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<const K: usize>(values: &[f64]) -> Vec<f64> {
+        let mut stage = diff::<f64, K>(|x| *x, |d, _| d);
+        let mut out = Vec::new();
+        for value in values {
+            stage.process(value, &mut |x: &f64| out.push(*x));
+        }
+        out
+    }
+
+    #[test]
+    fn test_first_difference_of_linear_sequence_is_constant() {
+        let values: Vec<f64> = (0..10).map(|i| 2.0 * i as f64 + 1.0).collect();
+        assert_eq!(run::<1>(&values), vec![2.0; 9]);
+    }
+
+    #[test]
+    fn test_lag_k_difference() {
+        let values = [1.0, 4.0, 9.0, 16.0, 25.0];
+        assert_eq!(run::<2>(&values), vec![8.0, 12.0, 16.0]);
+        assert_eq!(run::<5>(&values), Vec::<f64>::new());
+    }
+}
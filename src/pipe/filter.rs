@@ -37,6 +37,43 @@ pub fn filter<T: Pod + Send>(
     Filter::new(predicate)
 }
 
+/// Filters and transforms items in one step.
+///
+/// Items for which the function returns `None` are dropped.
+pub struct FilterMap<In, Out, F> {
+    f: F,
+    _phantom: PhantomData<(In, Out)>,
+}
+
+impl<In: Pod + Send, Out: Pod + Send, F: FnMut(&In) -> Option<Out>> FilterMap<In, Out, F> {
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<In: Pod + Send, Out: Pod + Send, F: FnMut(&In) -> Option<Out>> Stage<In, Out>
+    for FilterMap<In, Out, F>
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &In, collector: &mut C)
+    where
+        C: OutputCollector<Out>,
+    {
+        if let Some(out) = (self.f)(data) {
+            collector.push(&out);
+        }
+    }
+}
+
+pub fn filter_map<In: Pod + Send, Out: Pod + Send>(
+    f: impl FnMut(&In) -> Option<Out>,
+) -> FilterMap<In, Out, impl FnMut(&In) -> Option<Out>> {
+    FilterMap::new(f)
+}
+
 #[cfg(test)]
 mod filter_tests {
     use super::*;
@@ -51,4 +88,28 @@ mod filter_tests {
 
         assert_eq!(out, vec![10]);
     }
+
+    #[test]
+    fn test_filter_preserves_order() {
+        let mut pipe = filter(|x: &u32| x.is_multiple_of(3));
+        let mut out = Vec::new();
+
+        for i in 0..10u32 {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+
+        assert_eq!(out, vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_filter_map_logic() {
+        let mut pipe = filter_map(|x: &i32| (*x > 0).then(|| *x as u64 * 10));
+        let mut out = Vec::new();
+
+        for x in [1, -2, 3, 0, 5] {
+            pipe.process(&x, &mut |x: &u64| out.push(*x));
+        }
+
+        assert_eq!(out, vec![10, 30, 50]);
+    }
 }
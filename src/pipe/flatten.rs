@@ -0,0 +1,158 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::marker::PhantomData;
+
+/// A variable-length collection of up to `MAX` items that can travel through a pipeline.
+///
+/// Only the first `len` entries of `items` are meaningful. The struct is packed, so it is
+/// free of padding for any `T` and `MAX`; read the items with `iter` instead of borrowing.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct FlattenVecInput<T: Pod + Zeroable, const MAX: usize> {
+    pub len: u64,
+    pub items: [T; MAX],
+}
+
+unsafe impl<T: Pod + Zeroable, const MAX: usize> Zeroable for FlattenVecInput<T, MAX> {}
+unsafe impl<T: Pod + Zeroable, const MAX: usize> Pod for FlattenVecInput<T, MAX> {}
+
+impl<T: Pod + Zeroable, const MAX: usize> FlattenVecInput<T, MAX> {
+    /// Copies the items of `slice` into a new input.
+    ///
+    /// # Panics
+    /// Panics if `slice` has more than `MAX` items.
+    pub fn from_slice(slice: &[T]) -> Self {
+        assert!(
+            slice.len() <= MAX,
+            "Slice of {} items does not fit into {} slots",
+            slice.len(),
+            MAX
+        );
+        let mut items = [T::zeroed(); MAX];
+        items[..slice.len()].copy_from_slice(slice);
+        Self {
+            len: slice.len() as u64,
+            items,
+        }
+    }
+
+    /// Returns copies of the meaningful items.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let len = (self.len as usize).min(MAX);
+        bytemuck::bytes_of(self)[size_of::<u64>()..]
+            .chunks_exact(size_of::<T>())
+            .take(len)
+            .map(bytemuck::pod_read_unaligned)
+    }
+}
+
+/// Emits each element of a fixed-size array as a separate item.
+pub struct FlattenArray<T, const N: usize> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send, const N: usize> Stage<[T; N], T> for FlattenArray<T, N>
+where
+    [T; N]: Pod,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &[T; N], collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        for item in data {
+            collector.push(item);
+        }
+    }
+}
+
+/// Emits each item of a `FlattenVecInput` as a separate item, up to `max` per input.
+pub struct FlattenVec<T, const MAX: usize> {
+    max: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send, const MAX: usize> Stage<FlattenVecInput<T, MAX>, T> for FlattenVec<T, MAX> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &FlattenVecInput<T, MAX>, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        for item in data.iter().take(self.max) {
+            collector.push(&item);
+        }
+    }
+}
+
+pub fn flatten_array<T: Pod + Send, const N: usize>() -> FlattenArray<T, N>
+where
+    [T; N]: Pod,
+{
+    FlattenArray {
+        _phantom: PhantomData,
+    }
+}
+
+pub fn flatten_vec<T: Pod + Send, const MAX: usize>(max: usize) -> FlattenVec<T, MAX> {
+    FlattenVec {
+        max,
+        _phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_array_logic() {
+        let mut pipe = flatten_array::<u32, 3>();
+        let mut out = Vec::new();
+
+        pipe.process(&[1u32, 2, 3], &mut |x: &u32| out.push(*x));
+
+        assert_eq!(out, vec![1u32, 2, 3]);
+    }
+
+    #[test]
+    fn test_flatten_vec_logic() {
+        let mut pipe = flatten_vec::<u32, 8>(8);
+        let mut out = Vec::new();
+
+        pipe.process(&FlattenVecInput::from_slice(&[1, 2, 3]), &mut |x: &u32| {
+            out.push(*x)
+        });
+        pipe.process(&FlattenVecInput::from_slice(&[]), &mut |x: &u32| {
+            out.push(*x)
+        });
+        pipe.process(&FlattenVecInput::from_slice(&[4]), &mut |x: &u32| {
+            out.push(*x)
+        });
+
+        assert_eq!(out, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_flatten_vec_max() {
+        let mut pipe = flatten_vec::<u32, 8>(2);
+        let mut out = Vec::new();
+
+        pipe.process(&FlattenVecInput::from_slice(&[1, 2, 3]), &mut |x: &u32| {
+            out.push(*x)
+        });
+
+        assert_eq!(out, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_flatten_vec_odd_item_size() {
+        assert_eq!(size_of::<FlattenVecInput<u8, 3>>(), 11);
+
+        let mut pipe = flatten_vec::<u8, 3>(3);
+        let mut out = Vec::new();
+        pipe.process(&FlattenVecInput::from_slice(&[5, 6]), &mut |x: &u8| {
+            out.push(*x)
+        });
+        assert_eq!(out, vec![5, 6]);
+    }
+}
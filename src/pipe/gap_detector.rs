@@ -0,0 +1,134 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Emits an error item whenever the sequence numbers of the stream skip ahead.
+///
+/// The first item sets the expected sequence number. When an item arrives with a higher
+/// number than expected, `error_fn(expected, actual)` is emitted before the item itself.
+/// Items with a lower number than expected (duplicates or late items) are passed through
+/// without changing the expectation.
+pub struct GapDetector<T, F, E> {
+    seq_fn: F,
+    error_fn: E,
+    expected: Option<u64>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, F, E> GapDetector<T, F, E>
+where
+    T: Pod + Send,
+    F: Fn(&T) -> u64,
+    E: Fn(u64, u64) -> T,
+{
+    pub fn new(seq_fn: F, error_fn: E) -> Self {
+        Self {
+            seq_fn,
+            error_fn,
+            expected: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, F, E> Stage<T, T> for GapDetector<T, F, E>
+where
+    T: Pod + Send,
+    F: Fn(&T) -> u64,
+    E: Fn(u64, u64) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let actual = (self.seq_fn)(data);
+        match self.expected {
+            Some(expected) if actual < expected => {}
+            Some(expected) => {
+                if actual > expected {
+                    collector.push(&(self.error_fn)(expected, actual));
+                }
+                self.expected = Some(actual + 1);
+            }
+            None => self.expected = Some(actual + 1),
+        }
+        collector.push(data);
+    }
+}
+
+/// Detects gaps in the sequence numbers extracted by `seq_fn`, emitting
+/// `error_fn(expected, actual)` in front of the item that follows a gap.
+#[allow(clippy::type_complexity)]
+pub fn gap_detector<T: Pod + Send>(
+    seq_fn: impl Fn(&T) -> u64 + Send,
+    error_fn: impl Fn(u64, u64) -> T + Send,
+) -> GapDetector<T, impl Fn(&T) -> u64 + Send, impl Fn(u64, u64) -> T + Send> {
+    GapDetector::new(seq_fn, error_fn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Message {
+        seq: u64,
+        /// Number of missing messages for gap alerts, 0 for regular messages.
+        missing: u64,
+    }
+
+    fn msg(seq: u64) -> Message {
+        Message { seq, missing: 0 }
+    }
+
+    #[test]
+    fn test_gap_detector_emits_alerts_before_gaps() {
+        let mut pipe = gap_detector(
+            |m: &Message| m.seq,
+            |expected, actual| Message {
+                seq: expected,
+                missing: actual - expected,
+            },
+        );
+        let mut out = Vec::new();
+
+        for s in [3, 4, 5, 7, 8, 12, 13] {
+            pipe.process(&msg(s), &mut |m: &Message| out.push(*m));
+        }
+
+        assert_eq!(
+            out,
+            vec![
+                msg(3),
+                msg(4),
+                msg(5),
+                Message { seq: 6, missing: 1 },
+                msg(7),
+                msg(8),
+                Message { seq: 9, missing: 3 },
+                msg(12),
+                msg(13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gap_detector_passes_late_items() {
+        let mut pipe = gap_detector(
+            |m: &Message| m.seq,
+            |expected, actual| Message {
+                seq: expected,
+                missing: actual - expected,
+            },
+        );
+        let mut out = Vec::new();
+
+        for s in [0, 1, 1, 0, 2] {
+            pipe.process(&msg(s), &mut |m: &Message| out.push(*m));
+        }
+
+        assert_eq!(out, vec![msg(0), msg(1), msg(1), msg(0), msg(2)]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use fxhash::FxHashMap;
+use std::marker::PhantomData;
+
+/// Routes each item to a per-key sub-stage, creating the sub-stage on first use.
+///
+/// Each key gets its own stage instance, so any state kept by the sub-stage is
+/// isolated per key. All sub-stages write to the same downstream collector.
+pub struct GroupBy<K, T, S, KF, SF> {
+    key_fn: KF,
+    stage_factory: SF,
+    stages: FxHashMap<K, S>,
+    _phantom: PhantomData<T>,
+}
+
+impl<K, T, S, KF, SF> GroupBy<K, T, S, KF, SF>
+where
+    K: std::hash::Hash + Eq,
+    T: Pod + Send,
+    S: Stage<T, T>,
+    KF: Fn(&T) -> K,
+    SF: Fn(&K) -> S,
+{
+    pub fn new(key_fn: KF, stage_factory: SF) -> Self {
+        Self {
+            key_fn,
+            stage_factory,
+            stages: FxHashMap::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the keys that currently have a sub-stage.
+    pub fn iter_keys(&self) -> impl Iterator<Item = &K> {
+        self.stages.keys()
+    }
+}
+
+impl<K, T, S, KF, SF> Stage<T, T> for GroupBy<K, T, S, KF, SF>
+where
+    K: std::hash::Hash + Eq,
+    T: Pod + Send,
+    S: Stage<T, T>,
+    KF: Fn(&T) -> K,
+    SF: Fn(&K) -> S,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let key = (self.key_fn)(data);
+        let stage = self
+            .stages
+            .entry(key)
+            .or_insert_with_key(|k| (self.stage_factory)(k));
+        stage.process(data, collector);
+    }
+}
+
+pub fn group_by<T, K, S>(
+    key_fn: impl Fn(&T) -> K + Send,
+    stage_factory: impl Fn(&K) -> S + Send,
+) -> GroupBy<K, T, S, impl Fn(&T) -> K + Send, impl Fn(&K) -> S + Send>
+where
+    T: Pod + Send,
+    K: std::hash::Hash + Eq + Send,
+    S: Stage<T, T> + Send,
+{
+    GroupBy::new(key_fn, stage_factory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Reading {
+        id: u64,
+        value: f64,
+    }
+
+    struct Ewma {
+        alpha: f64,
+        value: Option<f64>,
+    }
+
+    impl Stage<Reading, Reading> for Ewma {
+        fn process<C>(&mut self, data: &Reading, collector: &mut C)
+        where
+            C: OutputCollector<Reading>,
+        {
+            let value = match self.value {
+                Some(prev) => prev + self.alpha * (data.value - prev),
+                None => data.value,
+            };
+            self.value = Some(value);
+            collector.push(&Reading { id: data.id, value });
+        }
+    }
+
+    #[test]
+    fn test_group_by_independent_state() {
+        let mut pipe = group_by(
+            |r: &Reading| r.id,
+            |_| Ewma {
+                alpha: 0.5,
+                value: None,
+            },
+        );
+        let mut out = Vec::new();
+
+        for (id, value) in [(1, 10.0), (2, 100.0), (1, 20.0), (2, 200.0)] {
+            pipe.process(&Reading { id, value }, &mut |r: &Reading| out.push(*r));
+        }
+
+        assert_eq!(
+            out,
+            vec![
+                Reading { id: 1, value: 10.0 },
+                Reading {
+                    id: 2,
+                    value: 100.0
+                },
+                Reading { id: 1, value: 15.0 },
+                Reading {
+                    id: 2,
+                    value: 150.0
+                },
+            ]
+        );
+
+        let mut keys: Vec<u64> = pipe.iter_keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, vec![1, 2]);
+    }
+}
@@ -33,6 +33,70 @@ pub fn inspect<T: Pod + Send>(f: impl FnMut(&T)) -> Inspect<T, impl FnMut(&T)> {
     Inspect::new(f)
 }
 
+/// Passes every item through but performs the side effect only on a subset of them:
+/// the first `first` items and every `nth` item (counting from zero).
+pub struct InspectSampled<T, F> {
+    f: F,
+    first: usize,
+    nth: usize,
+    count: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send, F: FnMut(&T)> InspectSampled<T, F> {
+    /// Creates a sampled inspector. A `nth` of `0` disables the periodic sampling.
+    pub fn new(first: usize, nth: usize, f: F) -> Self {
+        Self {
+            f,
+            first,
+            nth,
+            count: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod + Send, F: FnMut(&T)> Stage<T, T> for InspectSampled<T, F> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        if self.count < self.first || (self.nth > 0 && self.count.is_multiple_of(self.nth)) {
+            (self.f)(data);
+        }
+        self.count += 1;
+        collector.push(data);
+    }
+}
+
+/// Performs the side effect only for every `n`th item (items `0, n, 2n, ...`).
+pub fn inspect_nth<T: Pod + Send>(
+    n: usize,
+    f: impl FnMut(&T),
+) -> InspectSampled<T, impl FnMut(&T)> {
+    assert!(n > 0, "n must be greater than 0");
+    InspectSampled::new(0, n, f)
+}
+
+/// Performs the side effect only for the first `n` items, then becomes a pass-through.
+pub fn inspect_first<T: Pod + Send>(
+    n: usize,
+    f: impl FnMut(&T),
+) -> InspectSampled<T, impl FnMut(&T)> {
+    InspectSampled::new(n, 0, f)
+}
+
+/// Performs the side effect for the first `first` items and for every `nth` item.
+pub fn inspect_first_and_nth<T: Pod + Send>(
+    first: usize,
+    nth: usize,
+    f: impl FnMut(&T),
+) -> InspectSampled<T, impl FnMut(&T)> {
+    assert!(nth > 0, "nth must be greater than 0");
+    InspectSampled::new(first, nth, f)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,4 +117,48 @@ mod tests {
         assert_eq!(out, vec![42]);
         assert_eq!(count.load(Ordering::Relaxed), 1);
     }
+
+    #[test]
+    fn test_inspect_nth_logic() {
+        let mut seen = Vec::new();
+        let mut out = Vec::new();
+        {
+            let mut pipe = inspect_nth(3, |x: &u32| seen.push(*x));
+            for i in 0..99u32 {
+                pipe.process(&i, &mut |x: &u32| out.push(*x));
+            }
+        }
+
+        assert_eq!(seen.len(), 99 / 3);
+        assert_eq!(&seen[..3], &[0, 3, 6]);
+        assert_eq!(out.len(), 99);
+    }
+
+    #[test]
+    fn test_inspect_first_logic() {
+        let mut seen = Vec::new();
+        let mut out = Vec::new();
+        {
+            let mut pipe = inspect_first(2, |x: &u32| seen.push(*x));
+            for i in 0..10u32 {
+                pipe.process(&i, &mut |x: &u32| out.push(*x));
+            }
+        }
+
+        assert_eq!(seen, vec![0, 1]);
+        assert_eq!(out.len(), 10);
+    }
+
+    #[test]
+    fn test_inspect_first_and_nth_logic() {
+        let mut seen = Vec::new();
+        {
+            let mut pipe = inspect_first_and_nth(3, 5, |x: &u32| seen.push(*x));
+            for i in 0..12u32 {
+                pipe.process(&i, &mut |_: &u32| {});
+            }
+        }
+
+        assert_eq!(seen, vec![0, 1, 2, 5, 10]);
+    }
 }
@@ -0,0 +1,284 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Scalar Kalman filter for a value that follows a random walk.
+///
+/// `process_noise` (Q) is the variance of the change of the true value between two
+/// measurements and `measurement_noise` (R) the variance of the measurement error. An
+/// infinite `initial_uncertainty` means nothing is known yet, in which case the first
+/// measurement is taken as the estimate.
+#[derive(Debug, Clone)]
+pub struct KalmanFilter {
+    process_noise: f64,
+    measurement_noise: f64,
+    estimate: f64,
+    uncertainty: f64,
+    /// Weight of the newest step when adapting the noise variances, if adaptive.
+    adaptation_rate: Option<f64>,
+    /// Moving average of the squared innovation, used to adapt R.
+    innovation_variance: Option<f64>,
+}
+
+impl KalmanFilter {
+    pub fn new(
+        process_noise: f64,
+        measurement_noise: f64,
+        initial_estimate: f64,
+        initial_uncertainty: f64,
+    ) -> Self {
+        Self {
+            process_noise,
+            measurement_noise,
+            estimate: initial_estimate,
+            uncertainty: initial_uncertainty,
+            adaptation_rate: None,
+            innovation_variance: None,
+        }
+    }
+
+    /// Makes the filter re-estimate Q and R after every update.
+    ///
+    /// Both use exponential moving averages with weight `rate` for the newest step. R is the
+    /// average squared innovation minus the predicted uncertainty, and Q the average squared
+    /// correction applied to the estimate.
+    pub fn adaptive(mut self, rate: f64) -> Self {
+        assert!(rate > 0.0 && rate <= 1.0, "rate must be in (0, 1]");
+        self.adaptation_rate = Some(rate);
+        self
+    }
+
+    /// Runs one predict-update cycle with `measurement` and returns the new estimate.
+    pub fn update(&mut self, measurement: f64) -> f64 {
+        if self.uncertainty.is_infinite() {
+            self.estimate = measurement;
+            self.uncertainty = self.measurement_noise;
+            return self.estimate;
+        }
+
+        let predicted_uncertainty = self.uncertainty + self.process_noise;
+        let innovation = measurement - self.estimate;
+        let gain = predicted_uncertainty / (predicted_uncertainty + self.measurement_noise);
+        self.estimate += gain * innovation;
+        self.uncertainty = (1.0 - gain) * predicted_uncertainty;
+
+        if let Some(rate) = self.adaptation_rate {
+            let squared = innovation * innovation;
+            let variance = match self.innovation_variance {
+                Some(variance) => (1.0 - rate) * variance + rate * squared,
+                None => squared,
+            };
+            self.innovation_variance = Some(variance);
+            self.measurement_noise = (variance - predicted_uncertainty).max(f64::MIN_POSITIVE);
+            let correction = gain * innovation;
+            self.process_noise = (1.0 - rate) * self.process_noise + rate * correction * correction;
+        }
+
+        self.estimate
+    }
+
+    pub fn estimate(&self) -> f64 {
+        self.estimate
+    }
+
+    pub fn uncertainty(&self) -> f64 {
+        self.uncertainty
+    }
+
+    pub fn process_noise(&self) -> f64 {
+        self.process_noise
+    }
+
+    pub fn measurement_noise(&self) -> f64 {
+        self.measurement_noise
+    }
+}
+
+/// Smooths the value extracted by `measurement_fn` with a `KalmanFilter`.
+///
+/// The filtered estimate is passed to `output_fn` together with the item.
+pub struct KalmanStage<T, M, O> {
+    filter: KalmanFilter,
+    measurement_fn: M,
+    output_fn: O,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, M, O> KalmanStage<T, M, O>
+where
+    T: Pod + Send,
+    M: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    pub fn new(filter: KalmanFilter, measurement_fn: M, output_fn: O) -> Self {
+        Self {
+            filter,
+            measurement_fn,
+            output_fn,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn filter(&self) -> &KalmanFilter {
+        &self.filter
+    }
+}
+
+impl<T, M, O> Stage<T, T> for KalmanStage<T, M, O>
+where
+    T: Pod + Send,
+    M: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let estimate = self.filter.update((self.measurement_fn)(data));
+        collector.push(&(self.output_fn)(estimate, data));
+    }
+}
+
+/// Smooths a measurement with a Kalman filter that starts from the first measurement.
+pub fn kalman<T: Pod + Send>(
+    process_noise: f64,
+    measurement_noise: f64,
+    measurement_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> KalmanStage<T, impl Fn(&T) -> f64 + Send, impl Fn(f64, &T) -> T + Send> {
+    KalmanStage::new(
+        KalmanFilter::new(process_noise, measurement_noise, 0.0, f64::INFINITY),
+        measurement_fn,
+        output_fn,
+    )
+}
+
+/// Like `kalman`, but the noise variances are only initial guesses that are adapted with
+/// weight `rate`. See `KalmanFilter::adaptive`.
+pub fn adaptive_kalman<T: Pod + Send>(
+    process_noise: f64,
+    measurement_noise: f64,
+    rate: f64,
+    measurement_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> KalmanStage<T, impl Fn(&T) -> f64 + Send, impl Fn(f64, &T) -> T + Send> {
+    KalmanStage::new(
+        KalmanFilter::new(process_noise, measurement_noise, 0.0, f64::INFINITY).adaptive(rate),
+        measurement_fn,
+        output_fn,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Sample {
+        truth: f64,
+        measured: f64,
+        filtered: f64,
+    }
+
+    /// Standard normal samples from a seeded XorShift PRNG via the Box-Muller transform.
+    fn gaussian(state: &mut u64) -> f64 {
+        let mut uniform = || {
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            ((*state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+        let (u1, u2) = (uniform(), uniform());
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// A random walk with step deviation 0.1 measured with noise deviation 2.0.
+    fn samples() -> Vec<Sample> {
+        let mut state = 42;
+        let mut truth = 100.0;
+        (0..5_000)
+            .map(|_| {
+                truth += 0.1 * gaussian(&mut state);
+                Sample {
+                    truth,
+                    measured: truth + 2.0 * gaussian(&mut state),
+                    filtered: 0.0,
+                }
+            })
+            .collect()
+    }
+
+    fn rmse(samples: &[Sample], value_fn: impl Fn(&Sample) -> f64) -> f64 {
+        let sum: f64 = samples
+            .iter()
+            .map(|s| (value_fn(s) - s.truth).powi(2))
+            .sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    fn run<S: Stage<Sample, Sample>>(mut stage: S, samples: &[Sample]) -> Vec<Sample> {
+        let mut out = Vec::new();
+        for s in samples {
+            stage.process(s, &mut |s: &Sample| out.push(*s));
+        }
+        out
+    }
+
+    #[test]
+    fn test_kalman_reduces_noise() {
+        let samples = samples();
+        let out = run(
+            kalman(
+                0.01,
+                4.0,
+                |s: &Sample| s.measured,
+                |filtered, s: &Sample| Sample { filtered, ..*s },
+            ),
+            &samples,
+        );
+        let raw = rmse(&out, |s| s.measured);
+        let filtered = rmse(&out, |s| s.filtered);
+        assert!((raw - 2.0).abs() < 0.1, "raw rmse {}", raw);
+        assert!(filtered < raw / 2.0, "filtered {} vs raw {}", filtered, raw);
+    }
+
+    #[test]
+    fn test_kalman_first_measurement_is_estimate() {
+        let mut filter = KalmanFilter::new(0.01, 4.0, 0.0, f64::INFINITY);
+        assert_eq!(filter.update(7.0), 7.0);
+        assert_eq!(filter.uncertainty(), 4.0);
+
+        let mut filter = KalmanFilter::new(0.0, 1.0, 0.0, 1.0);
+        assert_eq!(filter.update(2.0), 1.0);
+        assert_eq!(filter.uncertainty(), 0.5);
+    }
+
+    #[test]
+    fn test_adaptive_kalman_learns_noise() {
+        let samples = samples();
+        // Start with a measurement noise far below the real variance of 4.0.
+        let mut stage = adaptive_kalman(
+            0.01,
+            0.01,
+            0.01,
+            |s: &Sample| s.measured,
+            |filtered, s: &Sample| Sample { filtered, ..*s },
+        );
+        let mut out = Vec::new();
+        for s in &samples {
+            stage.process(s, &mut |s: &Sample| out.push(*s));
+        }
+
+        let measurement_noise = stage.filter().measurement_noise();
+        assert!(
+            measurement_noise > 2.0 && measurement_noise < 6.0,
+            "measurement noise {}",
+            measurement_noise
+        );
+        let raw = rmse(&out[1_000..], |s| s.measured);
+        let filtered = rmse(&out[1_000..], |s| s.filtered);
+        assert!(filtered < raw / 2.0, "filtered {} vs raw {}", filtered, raw);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::logging::logger;
 use crate::measure::latency_measurer::LatencyMeasurer;
 use crate::stage::{OutputCollector, Stage};
 use bytemuck::Pod;
@@ -54,7 +55,14 @@ where
         }
         self.count += 1;
         if self.count.is_multiple_of(self.report_interval) {
-            info!("[{}] Latency: {}", self.name, self.measurer.format_stats());
+            let stats = self.measurer.get_stats();
+            info!(
+                logger: logger(),
+                "[{}] Latency: {}",
+                self.name,
+                stats.format(),
+                kv: { stage = self.name.as_str(), count = self.count, p50 = stats.p50, p99 = stats.p99 }
+            );
         }
     }
 }
@@ -1,14 +1,23 @@
-use crate::stage::{OutputCollector, Stage};
+use crate::stage::{OutputCollector, Stage, StageOutput};
 use bytemuck::Pod;
 use std::marker::PhantomData;
 
 /// Transforms an item from one type to another.
+///
+/// The function may return the output directly, a reference to it, or an `Option`
+/// of either, in which case `None` emits nothing.
 pub struct Map<In, Out, F> {
     f: F,
     _phantom: PhantomData<(In, Out)>,
 }
 
-impl<In: Pod + Send, Out: Pod + Send, F: FnMut(&In) -> Out> Map<In, Out, F> {
+impl<In, Out, F, R> Map<In, Out, F>
+where
+    In: Pod + Send,
+    Out: Pod + Send,
+    F: FnMut(&In) -> R,
+    R: StageOutput<Out>,
+{
     pub fn new(f: F) -> Self {
         Self {
             f,
@@ -17,21 +26,45 @@ impl<In: Pod + Send, Out: Pod + Send, F: FnMut(&In) -> Out> Map<In, Out, F> {
     }
 }
 
-impl<In: Pod + Send, Out: Pod + Send, F: FnMut(&In) -> Out> Stage<In, Out> for Map<In, Out, F> {
+impl<In, Out, F: Clone> Clone for Map<In, Out, F> {
+    fn clone(&self) -> Self {
+        Self {
+            f: self.f.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<In, Out, F: Default> Default for Map<In, Out, F> {
+    fn default() -> Self {
+        Self {
+            f: F::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<In, Out, F, R> Stage<In, Out> for Map<In, Out, F>
+where
+    In: Pod + Send,
+    Out: Pod + Send,
+    F: FnMut(&In) -> R,
+    R: StageOutput<Out>,
+{
     #[inline(always)]
     fn process<C>(&mut self, data: &In, collector: &mut C)
     where
         C: OutputCollector<Out>,
     {
-        let out = (self.f)(data);
-        collector.push(&out);
+        (self.f)(data).push_to(collector);
     }
 }
 
-pub fn map<In, Out>(f: impl FnMut(&In) -> Out) -> Map<In, Out, impl FnMut(&In) -> Out>
+pub fn map<In, Out, R>(f: impl FnMut(&In) -> R) -> Map<In, Out, impl FnMut(&In) -> R>
 where
     In: Pod + Send,
     Out: Pod + Send,
+    R: StageOutput<Out>,
 {
     Map::new(f)
 }
@@ -39,6 +72,8 @@ where
 #[cfg(test)]
 mod map_tests {
     use super::*;
+    use crate::pipe;
+    use crate::pipe::filter;
 
     #[test]
     fn test_map_logic() {
@@ -50,4 +85,65 @@ mod map_tests {
 
         assert_eq!(out, vec![42u64]);
     }
+
+    #[test]
+    fn test_map_option_output() {
+        let mut pipe = map(|x: &u32| x.is_multiple_of(2).then_some(*x));
+        let mut out = Vec::new();
+
+        for i in 0..5u32 {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+
+        assert_eq!(out, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_chained_maps() {
+        let mut pipe = pipe![
+            map(|x: &u32| *x as u64),
+            map(|x: &u64| *x * 10),
+            map(|x: &u64| *x as f64 + 0.5),
+        ];
+        let mut out = Vec::new();
+
+        pipe.process(&4u32, &mut |x: &f64| out.push(*x));
+
+        assert_eq!(out, vec![40.5]);
+    }
+
+    #[test]
+    fn test_map_then_filter() {
+        let mut pipe = pipe![map(|x: &u32| *x * 3), filter(|x: &u32| *x > 5)];
+        let mut out = Vec::new();
+
+        for i in 0..4u32 {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+
+        assert_eq!(out, vec![6, 9]);
+    }
+
+    #[test]
+    fn test_map_non_send_closure() {
+        // Only `StageEngine` needs stages to be `Send`
+        let offset = std::rc::Rc::new(10u32);
+        let mut pipe = map(move |x: &u32| *x + *offset);
+        let mut out = Vec::new();
+
+        pipe.process(&1u32, &mut |x: &u32| out.push(*x));
+
+        assert_eq!(out, vec![11]);
+    }
+
+    #[test]
+    fn test_map_clone() {
+        let pipe = Map::new(|x: &u32| *x + 1);
+        let mut cloned = pipe.clone();
+        let mut out = Vec::new();
+
+        cloned.process(&1u32, &mut |x: &u32| out.push(*x));
+
+        assert_eq!(out, vec![2]);
+    }
 }
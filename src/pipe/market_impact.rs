@@ -0,0 +1,225 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Estimates the temporary price impact of trading `volume` given the average daily
+/// volume `adv` and the volatility `sigma`.
+///
+/// The impact is `price * sigma * model(volume / adv)`, expressed in price units, and is
+/// passed to `output_fn` together with the item. The stage keeps no state.
+pub struct MarketImpact<T, P, V, A, S, O> {
+    price_fn: P,
+    volume_fn: V,
+    adv_fn: A,
+    sigma_fn: S,
+    output_fn: O,
+    /// Maps the participation rate `volume / adv` to the impact per unit of volatility.
+    model: fn(f64) -> f64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, P, V, A, S, O> MarketImpact<T, P, V, A, S, O>
+where
+    T: Pod + Send,
+    P: Fn(&T) -> f64,
+    V: Fn(&T) -> f64,
+    A: Fn(&T) -> f64,
+    S: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    pub fn new(
+        price_fn: P,
+        volume_fn: V,
+        adv_fn: A,
+        sigma_fn: S,
+        output_fn: O,
+        model: fn(f64) -> f64,
+    ) -> Self {
+        Self {
+            price_fn,
+            volume_fn,
+            adv_fn,
+            sigma_fn,
+            output_fn,
+            model,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, P, V, A, S, O> Stage<T, T> for MarketImpact<T, P, V, A, S, O>
+where
+    T: Pod + Send,
+    P: Fn(&T) -> f64,
+    V: Fn(&T) -> f64,
+    A: Fn(&T) -> f64,
+    S: Fn(&T) -> f64,
+    O: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let participation = (self.volume_fn)(data) / (self.adv_fn)(data);
+        let impact = (self.price_fn)(data) * (self.sigma_fn)(data) * (self.model)(participation);
+        collector.push(&(self.output_fn)(impact, data));
+    }
+}
+
+/// Estimates temporary market impact with the Almgren-Chriss square-root model,
+/// `price * sigma * sqrt(volume / adv)`. Same as `square_root_impact`.
+#[allow(clippy::type_complexity)]
+pub fn market_impact<T: Pod + Send>(
+    price_fn: impl Fn(&T) -> f64 + Send,
+    volume_fn: impl Fn(&T) -> f64 + Send,
+    adv_fn: impl Fn(&T) -> f64 + Send,
+    sigma_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> MarketImpact<
+    T,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(f64, &T) -> T + Send,
+> {
+    square_root_impact(price_fn, volume_fn, adv_fn, sigma_fn, output_fn)
+}
+
+/// Estimates market impact as `price * sigma * sqrt(volume / adv)`.
+#[allow(clippy::type_complexity)]
+pub fn square_root_impact<T: Pod + Send>(
+    price_fn: impl Fn(&T) -> f64 + Send,
+    volume_fn: impl Fn(&T) -> f64 + Send,
+    adv_fn: impl Fn(&T) -> f64 + Send,
+    sigma_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> MarketImpact<
+    T,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(f64, &T) -> T + Send,
+> {
+    MarketImpact::new(price_fn, volume_fn, adv_fn, sigma_fn, output_fn, f64::sqrt)
+}
+
+/// Estimates market impact as `price * sigma * volume / adv`.
+#[allow(clippy::type_complexity)]
+pub fn linear_impact<T: Pod + Send>(
+    price_fn: impl Fn(&T) -> f64 + Send,
+    volume_fn: impl Fn(&T) -> f64 + Send,
+    adv_fn: impl Fn(&T) -> f64 + Send,
+    sigma_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> MarketImpact<
+    T,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(&T) -> f64 + Send,
+    impl Fn(f64, &T) -> T + Send,
+> {
+    MarketImpact::new(price_fn, volume_fn, adv_fn, sigma_fn, output_fn, |p| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Order {
+        price: f64,
+        volume: f64,
+        adv: f64,
+        sigma: f64,
+        impact: f64,
+    }
+
+    fn order(price: f64, volume: f64, adv: f64, sigma: f64) -> Order {
+        Order {
+            price,
+            volume,
+            adv,
+            sigma,
+            impact: 0.0,
+        }
+    }
+
+    fn run<S: Stage<Order, Order>>(mut stage: S, orders: &[Order]) -> Vec<f64> {
+        let mut out = Vec::new();
+        for o in orders {
+            stage.process(o, &mut |o: &Order| out.push(o.impact));
+        }
+        out
+    }
+
+    // Reference values from:
+    //   price * sigma * math.sqrt(volume / adv)  and  price * sigma * volume / adv
+    const ORDERS: [(f64, f64, f64, f64); 3] = [
+        (100.0, 10_000.0, 1_000_000.0, 0.02),
+        (52.5, 250_000.0, 4_000_000.0, 0.015),
+        (10.0, 0.0, 500_000.0, 0.3),
+    ];
+    const SQRT_EXPECTED: [f64; 3] = [0.2, 0.196875, 0.0];
+    const LINEAR_EXPECTED: [f64; 3] = [0.02, 0.04921875, 0.0];
+
+    fn orders() -> Vec<Order> {
+        ORDERS
+            .iter()
+            .map(|&(p, v, a, s)| order(p, v, a, s))
+            .collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{} != {}", a, e);
+        }
+    }
+
+    #[test]
+    fn test_market_impact_square_root() {
+        let out = run(
+            market_impact(
+                |o: &Order| o.price,
+                |o: &Order| o.volume,
+                |o: &Order| o.adv,
+                |o: &Order| o.sigma,
+                |impact, o: &Order| Order { impact, ..*o },
+            ),
+            &orders(),
+        );
+        assert_close(&out, &SQRT_EXPECTED);
+
+        let out = run(
+            square_root_impact(
+                |o: &Order| o.price,
+                |o: &Order| o.volume,
+                |o: &Order| o.adv,
+                |o: &Order| o.sigma,
+                |impact, o: &Order| Order { impact, ..*o },
+            ),
+            &orders(),
+        );
+        assert_close(&out, &SQRT_EXPECTED);
+    }
+
+    #[test]
+    fn test_linear_impact() {
+        let out = run(
+            linear_impact(
+                |o: &Order| o.price,
+                |o: &Order| o.volume,
+                |o: &Order| o.adv,
+                |o: &Order| o.sigma,
+                |impact, o: &Order| Order { impact, ..*o },
+            ),
+            &orders(),
+        );
+        assert_close(&out, &LINEAR_EXPECTED);
+    }
+}
@@ -4,20 +4,65 @@
 
 mod dedup_by;
 mod delta;
+mod diff;
 mod filter;
+mod flatten;
+mod gap_detector;
+mod group_by;
 mod inspect;
+mod kalman;
 mod latency;
 mod map;
+mod market_impact;
+mod normalize;
+mod pack;
+mod persist;
 mod progress;
+mod regime;
+mod resequence;
+mod retry;
+mod sample;
+mod sequence_number;
+mod slice;
 mod stateful;
+mod timestamp;
 mod track;
+mod watermark;
 
-pub use dedup_by::dedup_by;
+pub use dedup_by::{
+    PodBytes, dedup_by, dedup_by_bytes, dedup_by_bytes_bounded, dedup_by_time_window,
+    dedup_by_value, dedup_by_value_bounded, dedup_by_window,
+};
 pub use delta::delta;
-pub use filter::filter;
-pub use inspect::inspect;
+pub use diff::diff;
+pub use filter::{filter, filter_map};
+pub use flatten::{FlattenVecInput, flatten_array, flatten_vec};
+pub use gap_detector::gap_detector;
+pub use group_by::group_by;
+pub use inspect::{inspect, inspect_first, inspect_first_and_nth, inspect_nth};
+pub use kalman::{KalmanFilter, adaptive_kalman, kalman};
 pub use latency::latency;
 pub use map::map;
-pub use progress::progress;
+pub use market_impact::{linear_impact, market_impact, square_root_impact};
+pub use normalize::normalize;
+pub use pack::{Pair, pack, unpack};
+pub use persist::{Persist, persist};
+pub use progress::{progress, progress_with_clock};
+pub use regime::{
+    REGIME_HIGH, REGIME_LOW, REGIME_MID, RegimeId, RegimeStage, RegimeState, RegimeTagged,
+    threshold_regime,
+};
+pub use resequence::resequence;
+pub use retry::{retry, retry_with_dead_letter};
+pub use sample::{sample, sample_random};
+pub use sequence_number::{
+    Sequenced, sequence_number, sequence_number_starting_at, strip_sequence,
+};
+pub use slice::{skip, slice, take};
 pub use stateful::stateful;
+pub use timestamp::{
+    Timestamped, strip_timestamp, timestamp_inject, timestamp_inject_with_clock, timestamp_nanos,
+    tsc_to_ns_calibration,
+};
 pub use track::{Tracked, track_prev, track_prev_by_hashmap};
+pub use watermark::{WatermarkState, watermark};
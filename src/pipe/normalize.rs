@@ -0,0 +1,118 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Applies min-max normalisation over a rolling window of the last `WINDOW` values.
+///
+/// The window is kept in a fixed-size stack buffer, so the stage never allocates.
+/// If every value in the window is equal, the normalised value is `0.5`.
+pub struct Normalize<T, const WINDOW: usize, VF, OF> {
+    value_fn: VF,
+    output_fn: OF,
+    buffer: [f64; WINDOW],
+    len: usize,
+    pos: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const WINDOW: usize, VF, OF> Normalize<T, WINDOW, VF, OF>
+where
+    T: Pod + Send,
+    VF: Fn(&T) -> f64,
+    OF: Fn(f64, &T) -> T,
+{
+    pub fn new(value_fn: VF, output_fn: OF) -> Self {
+        assert!(WINDOW > 0, "WINDOW must be greater than 0");
+        Self {
+            value_fn,
+            output_fn,
+            buffer: [0.0; WINDOW],
+            len: 0,
+            pos: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, const WINDOW: usize, VF, OF> Stage<T, T> for Normalize<T, WINDOW, VF, OF>
+where
+    T: Pod + Send,
+    VF: Fn(&T) -> f64,
+    OF: Fn(f64, &T) -> T,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let value = (self.value_fn)(data);
+        self.buffer[self.pos] = value;
+        self.pos = (self.pos + 1) % WINDOW;
+        if self.len < WINDOW {
+            self.len += 1;
+        }
+
+        let window = &self.buffer[..self.len];
+        let min = window.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = window.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        let normalized = if max == min {
+            0.5
+        } else {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        };
+
+        collector.push(&(self.output_fn)(normalized, data));
+    }
+}
+
+pub fn normalize<T: Pod + Send, const WINDOW: usize>(
+    value_fn: impl Fn(&T) -> f64 + Send,
+    output_fn: impl Fn(f64, &T) -> T + Send,
+) -> Normalize<T, WINDOW, impl Fn(&T) -> f64 + Send, impl Fn(f64, &T) -> T + Send> {
+    Normalize::new(value_fn, output_fn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_increasing_sequence() {
+        let mut pipe = normalize::<f64, 4>(|x| *x, |n, _| n);
+        let mut out = Vec::new();
+
+        for i in 0..20 {
+            pipe.process(&(i as f64), &mut |x: &f64| out.push(*x));
+        }
+
+        assert_eq!(out.len(), 20);
+        assert!(out.iter().all(|x| (0.0..=1.0).contains(x)));
+        assert!(out.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_normalize_constant_window() {
+        let mut pipe = normalize::<f64, 3>(|x| *x, |n, _| n);
+        let mut out = Vec::new();
+
+        for _ in 0..5 {
+            pipe.process(&7.0, &mut |x: &f64| out.push(*x));
+        }
+
+        assert_eq!(out, vec![0.5; 5]);
+    }
+
+    #[test]
+    fn test_normalize_rolls_window() {
+        let mut pipe = normalize::<f64, 2>(|x| *x, |n, _| n);
+        let mut out = Vec::new();
+
+        for v in [0.0, 10.0, 5.0, 5.0] {
+            pipe.process(&v, &mut |x: &f64| out.push(*x));
+        }
+
+        // Window [0, 10] -> 1.0, window [10, 5] -> 0.0, window [5, 5] -> 0.5
+        assert_eq!(out, vec![0.5, 1.0, 0.0, 0.5]);
+    }
+}
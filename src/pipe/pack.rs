@@ -0,0 +1,181 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::marker::PhantomData;
+
+/// A pair of values that can travel through a pipeline as a single item.
+///
+/// Tuples are not `Pod`, so `unpack` emits a `Pair` and `pack` consumes one. The struct is
+/// packed, so any `A` and `B` fit together without padding, e.g. `Pair<u8, u64>`.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pair<A: Pod + Zeroable, B: Pod + Zeroable> {
+    pub a: A,
+    pub b: B,
+}
+
+unsafe impl<A: Pod + Zeroable, B: Pod + Zeroable> Zeroable for Pair<A, B> {}
+unsafe impl<A: Pod + Zeroable, B: Pod + Zeroable> Pod for Pair<A, B> {}
+
+impl<A: Pod + Zeroable, B: Pod + Zeroable> Pair<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    /// Returns the pair as a tuple.
+    pub fn into_tuple(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A: Pod + Zeroable, B: Pod + Zeroable> From<(A, B)> for Pair<A, B> {
+    fn from((a, b): (A, B)) -> Self {
+        Self::new(a, b)
+    }
+}
+
+impl<A: Pod + Zeroable, B: Pod + Zeroable> From<Pair<A, B>> for (A, B) {
+    fn from(pair: Pair<A, B>) -> Self {
+        pair.into_tuple()
+    }
+}
+
+/// Combines the two values of a `Pair` into a single output item.
+pub struct PackStage<A, B, Out, F> {
+    f: F,
+    _phantom: PhantomData<(A, B, Out)>,
+}
+
+impl<A, B, Out, F> Stage<Pair<A, B>, Out> for PackStage<A, B, Out, F>
+where
+    A: Pod + Send,
+    B: Pod + Send,
+    Out: Pod + Send,
+    F: Fn(&A, &B) -> Out,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &Pair<A, B>, collector: &mut C)
+    where
+        C: OutputCollector<Out>,
+    {
+        let Pair { a, b } = *data;
+        collector.push(&(self.f)(&a, &b));
+    }
+}
+
+/// Splits an item into two values emitted as a `Pair`.
+pub struct UnpackStage<In, A, B, F> {
+    f: F,
+    _phantom: PhantomData<(In, A, B)>,
+}
+
+impl<In, A, B, F> Stage<In, Pair<A, B>> for UnpackStage<In, A, B, F>
+where
+    In: Pod + Send,
+    A: Pod + Send,
+    B: Pod + Send,
+    F: Fn(&In) -> (A, B),
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &In, collector: &mut C)
+    where
+        C: OutputCollector<Pair<A, B>>,
+    {
+        collector.push(&(self.f)(data).into());
+    }
+}
+
+pub fn pack<A: Pod + Send, B: Pod + Send, Out: Pod + Send>(
+    f: impl Fn(&A, &B) -> Out + Send,
+) -> PackStage<A, B, Out, impl Fn(&A, &B) -> Out + Send> {
+    PackStage {
+        f,
+        _phantom: PhantomData,
+    }
+}
+
+pub fn unpack<In: Pod + Send, A: Pod + Send, B: Pod + Send>(
+    f: impl Fn(&In) -> (A, B) + Send,
+) -> UnpackStage<In, A, B, impl Fn(&In) -> (A, B) + Send> {
+    UnpackStage {
+        f,
+        _phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipe;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Trade {
+        price: f64,
+        volume: u64,
+    }
+
+    #[test]
+    fn test_unpack_logic() {
+        let mut pipe = unpack(|t: &Trade| (t.price, t.volume));
+        let mut out = Vec::new();
+
+        pipe.process(
+            &Trade {
+                price: 10.5,
+                volume: 3,
+            },
+            &mut |x: &Pair<f64, u64>| out.push(x.into_tuple()),
+        );
+
+        assert_eq!(out, vec![(10.5, 3)]);
+    }
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        let mut pipe = pipe![
+            unpack(|t: &Trade| (t.price, t.volume)),
+            pack(|price: &f64, volume: &u64| Trade {
+                price: *price,
+                volume: *volume,
+            }),
+        ];
+        let mut out = Vec::new();
+
+        let trades = [
+            Trade {
+                price: 1.0,
+                volume: 10,
+            },
+            Trade {
+                price: 2.5,
+                volume: 20,
+            },
+        ];
+        for t in &trades {
+            pipe.process(t, &mut |x: &Trade| out.push(*x));
+        }
+
+        assert_eq!(out, trades.to_vec());
+    }
+
+    #[test]
+    fn test_pair_of_mixed_sizes() {
+        assert_eq!(size_of::<Pair<u8, u64>>(), 9);
+
+        let mut pipe = pipe![
+            unpack(|x: &u64| (*x as u8, *x)),
+            pack(|a: &u8, b: &u64| *a as u64 + *b),
+        ];
+        let mut out = Vec::new();
+        pipe.process(&3, &mut |x: &u64| out.push(*x));
+        assert_eq!(out, vec![6]);
+    }
+
+    #[test]
+    fn test_pair_conversions() {
+        let pair: Pair<u32, u32> = (1, 2).into();
+        assert_eq!(pair, Pair::new(1, 2));
+        let tuple: (u32, u32) = pair.into();
+        assert_eq!(tuple, (1, 2));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::stage::{OutputCollector, Stage};
+use crate::storage::journal_mmap::JournalMmap;
+use crate::util::crc32c;
+use bytemuck::Pod;
+use std::io;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+/// Writes every item to a write-ahead log file before passing it downstream.
+///
+/// Each record is the item followed by the CRC-32C of its bytes, and is synced to disk
+/// (`msync`) before the item is forwarded, so items seen downstream survive a crash.
+/// `Persist::recover` returns the complete records of a log; a record that was only
+/// partially written fails its checksum and ends the recovery.
+pub struct Persist<T> {
+    wal: JournalMmap,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send> Persist<T> {
+    const RECORD_SIZE: usize = size_of::<T>() + size_of::<u32>();
+
+    /// Opens the log at `wal_path` with room for `capacity` records.
+    ///
+    /// An existing log is kept and appended to after its last complete record.
+    pub fn new(wal_path: PathBuf, capacity: usize) -> io::Result<Self> {
+        let len = capacity * Self::RECORD_SIZE;
+        let wal = if wal_path.exists() {
+            let mut wal = JournalMmap::load(wal_path)?;
+            let complete = complete_records::<T>(wal.read_window::<u8>(0, wal.len())).count();
+            wal.set_write_index(complete * Self::RECORD_SIZE);
+            wal.extend(len)?;
+            wal
+        } else {
+            JournalMmap::new(Some(wal_path), len)?
+        };
+        Ok(Self {
+            wal,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Reads all complete records from the log at `wal_path`.
+    ///
+    /// Returns an empty list if the log does not exist.
+    pub fn recover(wal_path: PathBuf) -> io::Result<Vec<T>> {
+        if !wal_path.exists() {
+            return Ok(vec![]);
+        }
+        let wal = JournalMmap::load(wal_path)?;
+        Ok(complete_records(wal.read_window::<u8>(0, wal.len())).collect())
+    }
+}
+
+fn complete_records<T: Pod>(bytes: &[u8]) -> impl Iterator<Item = T> + '_ {
+    bytes
+        .chunks_exact(size_of::<T>() + size_of::<u32>())
+        .map_while(|record| {
+            let (item, crc) = record.split_at(size_of::<T>());
+            (crc32c(item) == u32::from_le_bytes(crc.try_into().unwrap()))
+                .then(|| bytemuck::pod_read_unaligned(item))
+        })
+}
+
+impl<T: Pod + Send> Stage<T, T> for Persist<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let offset = self.wal.get_write_index();
+        self.wal.append(data);
+        self.wal
+            .append(&crc32c(bytemuck::bytes_of(data)).to_le_bytes());
+        self.wal
+            .flush_range(offset, Self::RECORD_SIZE)
+            .expect("Failed to sync write-ahead log");
+        collector.push(data);
+    }
+}
+
+/// Persists every item to the write-ahead log at `wal_path` before passing it on.
+///
+/// See `Persist` for the record format and `Persist::recover` for reading it back.
+pub fn persist<T: Pod + Send>(wal_path: PathBuf, capacity: usize) -> io::Result<Persist<T>> {
+    Persist::new(wal_path, capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::{Seek, SeekFrom, Write};
+
+    fn wal_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("test_persist_{}_{}.wal", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn run(stage: &mut Persist<u64>, items: &[u64]) -> Vec<u64> {
+        let mut out = Vec::new();
+        for item in items {
+            stage.process(item, &mut |x: &u64| out.push(*x));
+        }
+        out
+    }
+
+    #[test]
+    fn test_persist_and_recover() {
+        let path = wal_path("recover");
+        assert!(Persist::<u64>::recover(path.clone()).unwrap().is_empty());
+
+        let mut stage = persist::<u64>(path.clone(), 16).unwrap();
+        assert_eq!(run(&mut stage, &[1, 2, 3]), vec![1, 2, 3]);
+        assert_eq!(
+            Persist::<u64>::recover(path.clone()).unwrap(),
+            vec![1, 2, 3]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recover_skips_partial_record() {
+        let path = wal_path("partial");
+        {
+            let mut stage = persist::<u64>(path.clone(), 16).unwrap();
+            run(&mut stage, &[10, 20, 30]);
+        }
+
+        // Crash after writing the first 4 bytes of the fourth record.
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(3 * 12)).unwrap();
+        file.write_all(&40u64.to_le_bytes()[..4]).unwrap();
+        drop(file);
+
+        assert_eq!(
+            Persist::<u64>::recover(path.clone()).unwrap(),
+            vec![10, 20, 30]
+        );
+
+        // Reopening resumes after the last complete record.
+        let mut stage = persist::<u64>(path.clone(), 16).unwrap();
+        run(&mut stage, &[40, 50]);
+        assert_eq!(
+            Persist::<u64>::recover(path.clone()).unwrap(),
+            vec![10, 20, 30, 40, 50]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
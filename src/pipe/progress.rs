@@ -1,35 +1,44 @@
+use crate::logging::logger;
+use crate::pipe::timestamp_nanos;
 use crate::stage::{OutputCollector, Stage};
 use bytemuck::Pod;
 use spdlog::info;
 use std::marker::PhantomData;
-use std::time::Instant;
 
-/// A pipe that logs progress information.
-pub struct Progress<T> {
+/// A pipe that logs progress information, reading the time in nanoseconds from `clock`.
+pub struct Progress<T, F = fn() -> u64> {
     name: String,
     interval: usize,
     count: usize,
-    last_instant: Instant,
-    start_instant: Instant,
+    clock: F,
+    last_ns: u64,
+    start_ns: u64,
     _phantom: PhantomData<T>,
 }
 
 impl<T: Pod + Send> Progress<T> {
     pub fn new(name: impl Into<String>, interval: usize) -> Self {
+        Self::with_clock(name, interval, timestamp_nanos)
+    }
+}
+
+impl<T: Pod + Send, F: Fn() -> u64> Progress<T, F> {
+    pub fn with_clock(name: impl Into<String>, interval: usize, clock: F) -> Self {
         assert!(interval > 0, "interval must be greater than 0");
-        let now = Instant::now();
+        let now = clock();
         Self {
             name: name.into(),
             interval,
             count: 0,
-            last_instant: now,
-            start_instant: now,
+            clock,
+            last_ns: now,
+            start_ns: now,
             _phantom: PhantomData,
         }
     }
 }
 
-impl<T: Pod + Send> Stage<T, T> for Progress<T> {
+impl<T: Pod + Send, F: Fn() -> u64> Stage<T, T> for Progress<T, F> {
     #[inline(always)]
     fn process<C>(&mut self, data: &T, collector: &mut C)
     where
@@ -37,21 +46,23 @@ impl<T: Pod + Send> Stage<T, T> for Progress<T> {
     {
         self.count += 1;
         if self.count.is_multiple_of(self.interval) {
-            let now = Instant::now();
-            let elapsed = now.duration_since(self.last_instant);
-            let total_elapsed = now.duration_since(self.start_instant);
+            let now = (self.clock)();
+            let elapsed_secs = now.saturating_sub(self.last_ns) as f64 / 1e9;
+            let total_elapsed_secs = now.saturating_sub(self.start_ns) as f64 / 1e9;
 
-            let mps = self.interval as f64 / elapsed.as_secs_f64();
-            let total_mps = self.count as f64 / total_elapsed.as_secs_f64();
+            let mps = self.interval as f64 / elapsed_secs;
+            let total_mps = self.count as f64 / total_elapsed_secs;
 
             info!(
+                logger: logger(),
                 "[{}] Processed {} msgs, Rate: {}/s, Avg: {}/s",
                 self.name,
                 format_count(self.count as f64),
                 format_count(mps),
-                format_count(total_mps)
+                format_count(total_mps),
+                kv: { stage = self.name.as_str(), count = self.count, rate = mps, avg_rate = total_mps }
             );
-            self.last_instant = now;
+            self.last_ns = now;
         }
         collector.push(data);
     }
@@ -61,6 +72,16 @@ pub fn progress<T: Pod + Send>(name: impl Into<String>, interval: usize) -> Prog
     Progress::new(name, interval)
 }
 
+/// Like `progress`, but reads the time in nanoseconds from `clock`, for example a
+/// `testing::MockClock`.
+pub fn progress_with_clock<T: Pod + Send>(
+    name: impl Into<String>,
+    interval: usize,
+    clock: impl Fn() -> u64 + Send,
+) -> Progress<T, impl Fn() -> u64 + Send> {
+    Progress::with_clock(name, interval, clock)
+}
+
 fn format_count(val: f64) -> String {
     if val < 1000.0 {
         if val == val.floor() {
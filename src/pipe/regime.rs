@@ -0,0 +1,249 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Identifies a regime of a `RegimeStage`.
+pub type RegimeId = u64;
+
+/// An item tagged with the regime that was current when it was processed.
+///
+/// Packed like `Sequenced`, so it is `Pod` for any `T`; copy fields out instead of
+/// borrowing them.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RegimeTagged<T: Pod + Zeroable> {
+    pub regime: RegimeId,
+    pub inner: T,
+}
+
+unsafe impl<T: Pod + Zeroable> Zeroable for RegimeTagged<T> {}
+unsafe impl<T: Pod + Zeroable> Pod for RegimeTagged<T> {}
+
+/// Describes one of the regimes a `RegimeStage` can be in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegimeState {
+    pub id: RegimeId,
+    pub name: &'static str,
+}
+
+impl RegimeState {
+    pub fn new(id: RegimeId, name: &'static str) -> Self {
+        Self { id, name }
+    }
+}
+
+/// Tags each item with the current regime.
+///
+/// The last `window` values extracted by `value_fn` are passed to `transition_fn` together
+/// with the current regime, and the regime it returns becomes current before the item is
+/// tagged. The stage starts in the first of `states`.
+pub struct RegimeStage<T, V, F> {
+    states: Vec<RegimeState>,
+    window: usize,
+    values: VecDeque<f64>,
+    value_fn: V,
+    transition_fn: F,
+    current: RegimeId,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, V, F> RegimeStage<T, V, F>
+where
+    T: Pod + Zeroable + Send,
+    V: Fn(&T) -> f64,
+    F: Fn(&[f64], RegimeId) -> RegimeId,
+{
+    pub fn new(states: Vec<RegimeState>, window: usize, value_fn: V, transition_fn: F) -> Self {
+        assert!(!states.is_empty(), "at least one regime is required");
+        assert!(window > 0, "window must not be zero");
+        Self {
+            current: states[0].id,
+            states,
+            window,
+            values: VecDeque::with_capacity(window),
+            value_fn,
+            transition_fn,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the regimes this stage was created with.
+    pub fn states(&self) -> &[RegimeState] {
+        &self.states
+    }
+
+    /// Returns the current regime.
+    pub fn current(&self) -> &RegimeState {
+        self.states
+            .iter()
+            .find(|state| state.id == self.current)
+            .expect("transition_fn returned an unknown regime")
+    }
+}
+
+impl<T, V, F> Stage<T, RegimeTagged<T>> for RegimeStage<T, V, F>
+where
+    T: Pod + Zeroable + Send,
+    V: Fn(&T) -> f64,
+    F: Fn(&[f64], RegimeId) -> RegimeId,
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<RegimeTagged<T>>,
+    {
+        if self.values.len() == self.window {
+            self.values.pop_front();
+        }
+        self.values.push_back((self.value_fn)(data));
+        self.current = (self.transition_fn)(self.values.make_contiguous(), self.current);
+        collector.push(&RegimeTagged {
+            regime: self.current,
+            inner: *data,
+        });
+    }
+}
+
+/// Regime of `threshold_regime` while the value is low.
+pub const REGIME_LOW: RegimeId = 0;
+/// Regime of `threshold_regime` while the value is between the thresholds.
+pub const REGIME_MID: RegimeId = 1;
+/// Regime of `threshold_regime` while the value is high.
+pub const REGIME_HIGH: RegimeId = 2;
+
+/// Tags items with `REGIME_LOW`, `REGIME_MID` or `REGIME_HIGH` based on `value_fn`.
+///
+/// The regime switches to high when the value reaches `high` and to low when it reaches
+/// `low`. To avoid flapping around a threshold, it only switches back to mid once the value
+/// has crossed the midpoint between `low` and `high`. The stage starts in mid.
+#[allow(clippy::type_complexity)]
+pub fn threshold_regime<T: Pod + Zeroable + Send>(
+    low: f64,
+    high: f64,
+    value_fn: impl Fn(&T) -> f64 + Send,
+) -> RegimeStage<T, impl Fn(&T) -> f64 + Send, impl Fn(&[f64], RegimeId) -> RegimeId + Send> {
+    assert!(low < high, "low must be below high");
+    let midpoint = (low + high) / 2.0;
+    RegimeStage::new(
+        vec![
+            RegimeState::new(REGIME_MID, "mid"),
+            RegimeState::new(REGIME_LOW, "low"),
+            RegimeState::new(REGIME_HIGH, "high"),
+        ],
+        1,
+        value_fn,
+        move |values: &[f64], current| {
+            let value = values[0];
+            if value >= high {
+                REGIME_HIGH
+            } else if value <= low {
+                REGIME_LOW
+            } else if (current == REGIME_HIGH && value <= midpoint)
+                || (current == REGIME_LOW && value >= midpoint)
+            {
+                REGIME_MID
+            } else {
+                current
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<S: Stage<f64, RegimeTagged<f64>>>(stage: &mut S, values: &[f64]) -> Vec<RegimeId> {
+        let mut out = Vec::new();
+        for v in values {
+            stage.process(v, &mut |t: &RegimeTagged<f64>| out.push(t.regime));
+        }
+        out
+    }
+
+    const TRENDING: RegimeId = 0;
+    const REVERTING: RegimeId = 1;
+
+    #[test]
+    fn test_regime_stage_trending_then_reverting() {
+        // Trending while the last four values move in one direction.
+        let mut stage = RegimeStage::new(
+            vec![
+                RegimeState::new(TRENDING, "trending"),
+                RegimeState::new(REVERTING, "reverting"),
+            ],
+            4,
+            |v: &f64| *v,
+            |values: &[f64], current| {
+                if values.len() < 4 {
+                    return current;
+                }
+                let up = values.windows(2).all(|w| w[1] > w[0]);
+                let down = values.windows(2).all(|w| w[1] < w[0]);
+                if up || down { TRENDING } else { REVERTING }
+            },
+        );
+        assert_eq!(stage.current().name, "trending");
+
+        let mut values: Vec<f64> = (0..8).map(|i| i as f64).collect();
+        values.extend([6.0, 8.0, 6.5, 7.5, 6.8, 7.2]);
+        let out = run(&mut stage, &values);
+
+        assert!(out[..8].iter().all(|r| *r == TRENDING));
+        assert!(out[8..].iter().all(|r| *r == REVERTING));
+        assert_eq!(stage.current().name, "reverting");
+    }
+
+    #[test]
+    fn test_threshold_regime_hysteresis() {
+        let mut stage = threshold_regime(10.0, 20.0, |v: &f64| *v);
+        let values = [15.0, 21.0, 19.0, 16.0, 14.0, 9.0, 11.0, 14.0, 16.0, 25.0];
+        let out = run(&mut stage, &values);
+        assert_eq!(
+            out,
+            vec![
+                REGIME_MID,
+                REGIME_HIGH,
+                REGIME_HIGH,
+                REGIME_HIGH,
+                REGIME_MID,
+                REGIME_LOW,
+                REGIME_LOW,
+                REGIME_LOW,
+                REGIME_MID,
+                REGIME_HIGH,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regime_tagged_keeps_item() {
+        let mut stage = threshold_regime(10.0, 20.0, |v: &f64| *v);
+        let mut out = Vec::new();
+        stage.process(&42.0, &mut |t: &RegimeTagged<f64>| out.push(*t));
+        assert_eq!(
+            out,
+            vec![RegimeTagged {
+                regime: REGIME_HIGH,
+                inner: 42.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_regime_tagged_small_item() {
+        assert_eq!(size_of::<RegimeTagged<u8>>(), 9);
+
+        let mut stage = threshold_regime(10.0, 20.0, |v: &u8| *v as f64);
+        let mut out = Vec::new();
+        stage.process(&5, &mut |t: &RegimeTagged<u8>| out.push(*t));
+        assert_eq!(
+            out,
+            vec![RegimeTagged {
+                regime: REGIME_LOW,
+                inner: 5
+            }]
+        );
+    }
+}
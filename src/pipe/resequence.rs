@@ -0,0 +1,148 @@
+use crate::pipe::Sequenced;
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A buffered item, ordered so that the smallest sequence number is at the top of the heap.
+struct Pending<T: Pod + Zeroable>(Sequenced<T>);
+
+impl<T: Pod + Zeroable> PartialEq for Pending<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.seq == other.0.seq
+    }
+}
+
+impl<T: Pod + Zeroable> Eq for Pending<T> {}
+
+impl<T: Pod + Zeroable> PartialOrd for Pending<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Pod + Zeroable> Ord for Pending<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        { other.0.seq }.cmp(&{ self.0.seq })
+    }
+}
+
+/// Restores sequence order of `Sequenced` items.
+///
+/// Items that arrive ahead of the expected sequence number are buffered. When more than
+/// `window` items are buffered, the missing sequence numbers are given up on and the
+/// buffer is flushed from its smallest entry. Items older than the expected sequence
+/// number (duplicates or items that were given up on) are dropped.
+pub struct Resequence<T: Pod + Zeroable> {
+    next_seq: u64,
+    window: usize,
+    pending: BinaryHeap<Pending<T>>,
+}
+
+impl<T: Pod + Zeroable + Send> Resequence<T> {
+    pub fn new(start: u64, window: usize) -> Self {
+        Self {
+            next_seq: start,
+            window,
+            pending: BinaryHeap::with_capacity(window + 1),
+        }
+    }
+
+    #[inline(always)]
+    fn flush<C: OutputCollector<Sequenced<T>>>(&mut self, collector: &mut C) {
+        while let Some(top) = self.pending.peek() {
+            if top.0.seq > self.next_seq {
+                break;
+            }
+            let Pending(item) = self.pending.pop().unwrap();
+            if item.seq == self.next_seq {
+                collector.push(&item);
+                self.next_seq += 1;
+            }
+        }
+    }
+}
+
+impl<T: Pod + Zeroable + Send> Stage<Sequenced<T>, Sequenced<T>> for Resequence<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &Sequenced<T>, collector: &mut C)
+    where
+        C: OutputCollector<Sequenced<T>>,
+    {
+        if data.seq < self.next_seq {
+            return;
+        }
+
+        if data.seq == self.next_seq {
+            collector.push(data);
+            self.next_seq += 1;
+        } else {
+            self.pending.push(Pending(*data));
+            if self.pending.len() > self.window {
+                // Give up on the gap and continue from the oldest buffered item
+                self.next_seq = self.pending.peek().unwrap().0.seq;
+            }
+        }
+
+        self.flush(collector);
+    }
+}
+
+/// Re-orders `Sequenced` items produced by `sequence_number`, buffering up to `window`
+/// out-of-order items.
+pub fn resequence<T: Pod + Zeroable + Send>(window: usize) -> Resequence<T> {
+    Resequence::new(0, window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq(seq: u64) -> Sequenced<u64> {
+        Sequenced {
+            seq,
+            inner: seq * 10,
+        }
+    }
+
+    #[test]
+    fn test_resequence_restores_order() {
+        let mut pipe = resequence::<u64>(8);
+        let mut out = Vec::new();
+
+        for s in [1, 0, 3, 2, 4, 6, 5] {
+            pipe.process(&seq(s), &mut |x: &Sequenced<u64>| out.push(x.seq));
+        }
+
+        assert_eq!(out, vec![0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_resequence_drops_duplicates() {
+        let mut pipe = resequence::<u64>(8);
+        let mut out = Vec::new();
+
+        for s in [0, 0, 1, 2, 1] {
+            pipe.process(&seq(s), &mut |x: &Sequenced<u64>| out.push(x.seq));
+        }
+
+        assert_eq!(out, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_resequence_skips_gap_when_window_full() {
+        let mut pipe = resequence::<u64>(2);
+        let mut out = Vec::new();
+
+        // Sequence number 1 never arrives
+        for s in [0, 2, 3, 4, 5] {
+            pipe.process(&seq(s), &mut |x: &Sequenced<u64>| out.push(x.seq));
+        }
+
+        assert_eq!(out, vec![0, 2, 3, 4, 5]);
+
+        // A late item for the skipped gap is dropped
+        pipe.process(&seq(1), &mut |x: &Sequenced<u64>| out.push(x.seq));
+        assert_eq!(out, vec![0, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Re-feeds an item to an inner stage while its output signals a failure.
+///
+/// Outputs for which `should_retry` returns true are treated as failures and are not
+/// forwarded. The item is processed at most `max_attempts` times; if the last attempt
+/// still fails, the item is handed to the dead-letter callback and dropped.
+pub struct Retry<T, S, R, D> {
+    max_attempts: usize,
+    inner: S,
+    should_retry: R,
+    dead_letter: D,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, S, R, D> Retry<T, S, R, D>
+where
+    T: Pod + Send,
+    S: Stage<T, T>,
+    R: Fn(&T) -> bool,
+    D: FnMut(&T),
+{
+    pub fn new(max_attempts: usize, inner: S, should_retry: R, dead_letter: D) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be greater than 0");
+        Self {
+            max_attempts,
+            inner,
+            should_retry,
+            dead_letter,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, S, R, D> Stage<T, T> for Retry<T, S, R, D>
+where
+    T: Pod + Send,
+    S: Stage<T, T>,
+    R: Fn(&T) -> bool,
+    D: FnMut(&T),
+{
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let should_retry = &self.should_retry;
+        for _ in 0..self.max_attempts {
+            let mut failed = false;
+            self.inner.process(data, &mut |out: &T| {
+                if should_retry(out) {
+                    failed = true;
+                } else {
+                    collector.push(out);
+                }
+            });
+            if !failed {
+                return;
+            }
+        }
+        (self.dead_letter)(data);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn retry<T: Pod + Send, S: Stage<T, T>>(
+    max_attempts: usize,
+    inner: S,
+    should_retry: impl Fn(&T) -> bool + Send,
+) -> Retry<T, S, impl Fn(&T) -> bool + Send, impl FnMut(&T) + Send> {
+    Retry::new(max_attempts, inner, should_retry, |_: &T| {})
+}
+
+/// Like `retry`, but calls `dead_letter` with every item that failed all attempts.
+#[allow(clippy::type_complexity)]
+pub fn retry_with_dead_letter<T: Pod + Send, S: Stage<T, T>>(
+    max_attempts: usize,
+    inner: S,
+    should_retry: impl Fn(&T) -> bool + Send,
+    dead_letter: impl FnMut(&T) + Send,
+) -> Retry<T, S, impl Fn(&T) -> bool + Send, impl FnMut(&T) + Send> {
+    Retry::new(max_attempts, inner, should_retry, dead_letter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Job {
+        id: u64,
+        failed: u64,
+    }
+
+    /// Fails the first `failures` attempts of every item.
+    struct Flaky {
+        failures: usize,
+        attempts: usize,
+    }
+
+    impl Stage<Job, Job> for Flaky {
+        fn process<C>(&mut self, data: &Job, collector: &mut C)
+        where
+            C: OutputCollector<Job>,
+        {
+            self.attempts += 1;
+            let failed = if self.attempts <= self.failures { 1 } else { 0 };
+            collector.push(&Job {
+                id: data.id,
+                failed,
+            });
+        }
+    }
+
+    #[test]
+    fn test_retry_succeeds_on_third_attempt() {
+        let mut pipe = retry(
+            3,
+            Flaky {
+                failures: 2,
+                attempts: 0,
+            },
+            |job: &Job| job.failed != 0,
+        );
+        let mut out = Vec::new();
+
+        pipe.process(&Job { id: 7, failed: 0 }, &mut |x: &Job| out.push(*x));
+
+        assert_eq!(out, vec![Job { id: 7, failed: 0 }]);
+        assert_eq!(pipe.inner.attempts, 3);
+    }
+
+    #[test]
+    fn test_retry_dead_letter() {
+        let mut dead = Vec::new();
+        let mut out = Vec::new();
+        {
+            let mut pipe = retry_with_dead_letter(
+                2,
+                Flaky {
+                    failures: 2,
+                    attempts: 0,
+                },
+                |job: &Job| job.failed != 0,
+                |job: &Job| dead.push(job.id),
+            );
+
+            pipe.process(&Job { id: 1, failed: 0 }, &mut |x: &Job| out.push(*x));
+            pipe.process(&Job { id: 2, failed: 0 }, &mut |x: &Job| out.push(*x));
+        }
+
+        assert_eq!(dead, vec![1]);
+        assert_eq!(out, vec![Job { id: 2, failed: 0 }]);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Passes every `rate`th item, starting from the first one.
+pub struct Sample<T> {
+    rate: usize,
+    count: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send> Sample<T> {
+    pub fn new(rate: usize) -> Self {
+        assert!(rate > 0, "rate must be greater than 0");
+        Self {
+            rate,
+            count: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod + Send> Stage<T, T> for Sample<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        if self.count == 0 {
+            collector.push(data);
+        }
+        self.count += 1;
+        if self.count == self.rate {
+            self.count = 0;
+        }
+    }
+}
+
+/// Passes each item with a fixed probability using a seeded XorShift PRNG.
+pub struct SampleRandom<T> {
+    probability: f64,
+    state: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send> SampleRandom<T> {
+    pub fn new(probability: f64, seed: u64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "probability must be within [0, 1]"
+        );
+        Self {
+            probability,
+            // XorShift gets stuck at zero
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+            _phantom: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        // Use the top 53 bits to get a uniform value in [0, 1)
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<T: Pod + Send> Stage<T, T> for SampleRandom<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        if self.next_f64() < self.probability {
+            collector.push(data);
+        }
+    }
+}
+
+pub fn sample<T: Pod + Send>(rate: usize) -> Sample<T> {
+    Sample::new(rate)
+}
+
+pub fn sample_random<T: Pod + Send>(probability: f64, seed: u64) -> SampleRandom<T> {
+    SampleRandom::new(probability, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<S: Stage<u32, u32>>(mut pipe: S, count: u32) -> Vec<u32> {
+        let mut out = Vec::new();
+        for i in 0..count {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+        out
+    }
+
+    #[test]
+    fn test_sample_logic() {
+        assert_eq!(run(sample(1), 10), (0..10).collect::<Vec<_>>());
+        assert_eq!(run(sample(2), 10), vec![0, 2, 4, 6, 8]);
+        assert_eq!(run(sample(3), 10), vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_sample_random_logic() {
+        assert_eq!(
+            run(sample_random(1.0, 42), 100),
+            (0..100).collect::<Vec<_>>()
+        );
+        assert!(run(sample_random(0.0, 42), 100).is_empty());
+
+        let half = run(sample_random(0.5, 42), 10_000).len();
+        assert!((4_000..6_000).contains(&half), "sampled {} items", half);
+
+        // Same seed gives the same selection
+        assert_eq!(
+            run(sample_random(0.3, 7), 1_000),
+            run(sample_random(0.3, 7), 1_000)
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::marker::PhantomData;
+
+/// An item tagged with a monotonically increasing sequence number.
+///
+/// Packed, so there is no padding for any `T` and the struct is `Pod`. Fields cannot be
+/// borrowed; copy them out instead (e.g. `{ item.inner }`).
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Sequenced<T: Pod + Zeroable> {
+    pub seq: u64,
+    pub inner: T,
+}
+
+unsafe impl<T: Pod + Zeroable> Zeroable for Sequenced<T> {}
+unsafe impl<T: Pod + Zeroable> Pod for Sequenced<T> {}
+
+/// Attaches a sequence number to each item, incrementing by one per item.
+pub struct SequenceNumber<T> {
+    next_seq: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Zeroable + Send> SequenceNumber<T> {
+    pub fn new(start: u64) -> Self {
+        Self {
+            next_seq: start,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod + Zeroable + Send> Stage<T, Sequenced<T>> for SequenceNumber<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<Sequenced<T>>,
+    {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        collector.push(&Sequenced { seq, inner: *data });
+    }
+}
+
+/// Removes the sequence number, emitting the wrapped item.
+pub struct StripSequence<T> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Zeroable + Send> Stage<Sequenced<T>, T> for StripSequence<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &Sequenced<T>, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        collector.push(&{ data.inner });
+    }
+}
+
+pub fn sequence_number<T: Pod + Send + Zeroable>() -> SequenceNumber<T> {
+    SequenceNumber::new(0)
+}
+
+/// Like `sequence_number`, but starts counting from `n` (e.g. after a checkpoint restore).
+pub fn sequence_number_starting_at<T: Pod + Send + Zeroable>(n: u64) -> SequenceNumber<T> {
+    SequenceNumber::new(n)
+}
+
+pub fn strip_sequence<T: Pod + Send + Zeroable>() -> StripSequence<T> {
+    StripSequence {
+        _phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipe;
+
+    #[test]
+    fn test_sequence_is_gapless() {
+        let mut pipe = sequence_number::<u64>();
+        let mut out = Vec::new();
+
+        for i in 0..10_000u64 {
+            pipe.process(&(i * 3), &mut |x: &Sequenced<u64>| out.push(*x));
+        }
+
+        assert_eq!(out.len(), 10_000);
+        for (i, item) in out.iter().enumerate() {
+            assert_eq!({ item.seq }, i as u64);
+            assert_eq!({ item.inner }, i as u64 * 3);
+        }
+    }
+
+    #[test]
+    fn test_sequence_starting_at() {
+        let mut pipe = sequence_number_starting_at::<u64>(100);
+        let mut out = Vec::new();
+
+        pipe.process(&1, &mut |x: &Sequenced<u64>| out.push(x.seq));
+        pipe.process(&2, &mut |x: &Sequenced<u64>| out.push(x.seq));
+
+        assert_eq!(out, vec![100, 101]);
+    }
+
+    #[test]
+    fn test_sequenced_has_no_padding() {
+        assert_eq!(size_of::<Sequenced<u32>>(), 12);
+        assert_eq!(size_of::<Sequenced<u8>>(), 9);
+
+        let mut pipe = pipe![sequence_number::<u32>(), strip_sequence::<u32>()];
+        let mut out = Vec::new();
+        for i in 0..10u32 {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+        assert_eq!(out, (0..10u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_strip_sequence_roundtrip() {
+        let mut pipe = pipe![sequence_number::<u64>(), strip_sequence::<u64>()];
+        let mut out = Vec::new();
+
+        for i in 0..100u64 {
+            pipe.process(&i, &mut |x: &u64| out.push(*x));
+        }
+
+        assert_eq!(out, (0..100u64).collect::<Vec<_>>());
+    }
+}
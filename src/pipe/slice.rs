@@ -0,0 +1,84 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Passes only the items whose position in the stream falls within `[start, end)`.
+pub struct Slice<T> {
+    start: usize,
+    end: usize,
+    count: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send> Slice<T> {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+            count: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod + Send> Stage<T, T> for Slice<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let position = self.count;
+        self.count = self.count.saturating_add(1);
+        if position >= self.start && position < self.end {
+            collector.push(data);
+        }
+    }
+}
+
+/// Discards the first `n` items, then passes all subsequent items.
+pub fn skip<T: Pod + Send>(n: usize) -> Slice<T> {
+    Slice::new(n, usize::MAX)
+}
+
+/// Passes the first `n` items and drops everything after.
+pub fn take<T: Pod + Send>(n: usize) -> Slice<T> {
+    Slice::new(0, n)
+}
+
+/// Skips `start` items, then passes the next `len` items.
+pub fn slice<T: Pod + Send>(start: usize, len: usize) -> Slice<T> {
+    Slice::new(start, start.saturating_add(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mut pipe: Slice<u32>, count: u32) -> Vec<u32> {
+        let mut out = Vec::new();
+        for i in 0..count {
+            pipe.process(&i, &mut |x: &u32| out.push(*x));
+        }
+        out
+    }
+
+    #[test]
+    fn test_skip_logic() {
+        let out = run(skip(3), 10);
+        assert_eq!(out.len(), 7);
+        assert_eq!(out, vec![3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_take_logic() {
+        let out = run(take(3), 10);
+        assert_eq!(out, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_slice_logic() {
+        assert_eq!(run(slice(2, 3), 10), vec![2, 3, 4]);
+        assert_eq!(run(slice(8, 5), 10), vec![8, 9]);
+        assert!(run(slice(12, 5), 10).is_empty());
+    }
+}
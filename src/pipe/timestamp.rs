@@ -0,0 +1,234 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::{Pod, Zeroable};
+use std::marker::PhantomData;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+/// An item tagged with the time it passed a `timestamp_inject` stage.
+///
+/// Packed like `Sequenced`, so it is `Pod` for any `T`; copy fields out instead of
+/// borrowing them.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timestamped<T: Pod + Zeroable> {
+    /// Nanoseconds since the timestamp clock was first used in this process.
+    pub ts_ns: u64,
+    pub inner: T,
+}
+
+unsafe impl<T: Pod + Zeroable> Zeroable for Timestamped<T> {}
+unsafe impl<T: Pod + Zeroable> Pod for Timestamped<T> {}
+
+/// Process-wide clock, calibrated once on first use.
+struct Clock {
+    #[cfg(not(target_arch = "x86_64"))]
+    start: Instant,
+    #[cfg(target_arch = "x86_64")]
+    start_tsc: u64,
+    #[cfg(target_arch = "x86_64")]
+    ns_per_tick: f64,
+}
+
+static CLOCK: LazyLock<Clock> = LazyLock::new(|| Clock {
+    #[cfg(target_arch = "x86_64")]
+    ns_per_tick: tsc_to_ns_calibration(),
+    #[cfg(target_arch = "x86_64")]
+    start_tsc: read_tsc(),
+    #[cfg(not(target_arch = "x86_64"))]
+    start: Instant::now(),
+});
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn read_tsc() -> u64 {
+    #[allow(unused_unsafe)]
+    unsafe {
+        std::arch::x86_64::_rdtsc()
+    }
+}
+
+/// Measures how many nanoseconds one TSC tick takes.
+///
+/// This busy-waits for about 10ms. On architectures without a TSC it returns `1.0`.
+pub fn tsc_to_ns_calibration() -> f64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let start = Instant::now();
+        let start_tsc = read_tsc();
+        while start.elapsed().as_millis() < 10 {
+            std::hint::spin_loop();
+        }
+        let ticks = read_tsc().wrapping_sub(start_tsc);
+        let elapsed = start.elapsed();
+        if ticks == 0 {
+            return 1.0;
+        }
+        elapsed.as_nanos() as f64 / ticks as f64
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        1.0
+    }
+}
+
+/// Returns nanoseconds since the timestamp clock was first used.
+///
+/// With the `testing` feature, the mock clock is returned inside `testing::with_mock_clock`.
+#[inline(always)]
+pub fn timestamp_nanos() -> u64 {
+    #[cfg(any(test, feature = "testing"))]
+    if let Some(now) = crate::testing::mock_now() {
+        return now;
+    }
+    let clock = &*CLOCK;
+    #[cfg(target_arch = "x86_64")]
+    {
+        let ticks = read_tsc().wrapping_sub(clock.start_tsc);
+        (ticks as f64 * clock.ns_per_tick) as u64
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        clock.start.elapsed().as_nanos() as u64
+    }
+}
+
+/// Wraps each item in a `Timestamped` with the current time read from `clock`.
+pub struct TimestampInject<T, F = fn() -> u64> {
+    clock: F,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Zeroable + Send, F: Fn() -> u64> Stage<T, Timestamped<T>> for TimestampInject<T, F> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<Timestamped<T>>,
+    {
+        collector.push(&Timestamped {
+            ts_ns: (self.clock)(),
+            inner: *data,
+        });
+    }
+}
+
+/// Removes the timestamp, emitting the wrapped item.
+pub struct StripTimestamp<T> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Zeroable + Send> Stage<Timestamped<T>, T> for StripTimestamp<T> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &Timestamped<T>, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        collector.push(&{ data.inner });
+    }
+}
+
+pub fn timestamp_inject<T: Pod + Send + Zeroable>() -> TimestampInject<T> {
+    // Calibrate up front instead of on the first item
+    LazyLock::force(&CLOCK);
+    TimestampInject {
+        clock: timestamp_nanos,
+        _phantom: PhantomData,
+    }
+}
+
+/// Like `timestamp_inject`, but reads the time in nanoseconds from `clock`, for example
+/// a `testing::MockClock`.
+pub fn timestamp_inject_with_clock<T: Pod + Send + Zeroable>(
+    clock: impl Fn() -> u64 + Send,
+) -> TimestampInject<T, impl Fn() -> u64 + Send> {
+    TimestampInject {
+        clock,
+        _phantom: PhantomData,
+    }
+}
+
+pub fn strip_timestamp<T: Pod + Send + Zeroable>() -> StripTimestamp<T> {
+    StripTimestamp {
+        _phantom: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipe;
+    use crate::testing::{MockClock, with_mock_clock};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timestamps_increase() {
+        let mut pipe = timestamp_inject::<u64>();
+        let mut out = Vec::new();
+
+        pipe.process(&1, &mut |x: &Timestamped<u64>| out.push(*x));
+        thread::sleep(Duration::from_millis(5));
+        pipe.process(&2, &mut |x: &Timestamped<u64>| out.push(*x));
+
+        assert_eq!({ out[0].inner }, 1);
+        assert_eq!({ out[1].inner }, 2);
+        let diff = out[1].ts_ns - out[0].ts_ns;
+        assert!(diff >= 4_000_000, "timestamps {} ns apart", diff);
+    }
+
+    #[test]
+    fn test_timestamps_from_mock_clock() {
+        let clock = MockClock::new(1_000);
+        let mut pipe = timestamp_inject_with_clock::<u64>(clock.as_fn());
+        let mut out = Vec::new();
+
+        pipe.process(&1, &mut |x: &Timestamped<u64>| out.push(x.ts_ns));
+        clock.advance(Duration::from_millis(10).as_nanos() as u64);
+        pipe.process(&2, &mut |x: &Timestamped<u64>| out.push(x.ts_ns));
+
+        assert_eq!(out, vec![1_000, 10_001_000]);
+    }
+
+    #[test]
+    fn test_timestamp_inject_with_mock_clock() {
+        let clock = MockClock::new(5);
+        let mut pipe = timestamp_inject::<u64>();
+        let mut out = Vec::new();
+
+        with_mock_clock(clock.clone(), || {
+            pipe.process(&1, &mut |x: &Timestamped<u64>| out.push(x.ts_ns));
+            clock.advance(20);
+            pipe.process(&2, &mut |x: &Timestamped<u64>| out.push(x.ts_ns));
+        });
+        assert_eq!(out, vec![5, 25]);
+
+        pipe.process(&3, &mut |x: &Timestamped<u64>| out.push(x.ts_ns));
+        assert_ne!(out[2], 25);
+    }
+
+    #[test]
+    fn test_calibration_is_positive() {
+        assert!(tsc_to_ns_calibration() > 0.0);
+    }
+
+    #[test]
+    fn test_strip_timestamp_roundtrip() {
+        let mut pipe = pipe![timestamp_inject::<u64>(), strip_timestamp::<u64>()];
+        let mut out = Vec::new();
+
+        for i in 0..10u64 {
+            pipe.process(&i, &mut |x: &u64| out.push(*x));
+        }
+
+        assert_eq!(out, (0..10u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_timestamped_has_no_padding() {
+        assert_eq!(size_of::<Timestamped<u32>>(), 12);
+
+        let mut pipe = pipe![timestamp_inject::<u32>(), strip_timestamp::<u32>()];
+        let mut out = Vec::new();
+        pipe.process(&7, &mut |x: &u32| out.push(*x));
+        assert_eq!(out, vec![7]);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::stage::{OutputCollector, Stage};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Shared view of the event-time watermark published by a `Watermark` stage.
+///
+/// It can be cloned and moved to downstream stages (or other threads) to decide
+/// when a time bucket can be closed.
+#[derive(Clone, Default)]
+pub struct WatermarkState {
+    watermark: Arc<AtomicU64>,
+    late_count: Arc<AtomicU64>,
+}
+
+impl WatermarkState {
+    /// Returns the current watermark in the same unit as the item timestamps.
+    #[inline(always)]
+    pub fn current_watermark(&self) -> u64 {
+        self.watermark.load(Acquire)
+    }
+
+    /// Returns how many items arrived with a timestamp below the watermark.
+    pub fn late_count(&self) -> u64 {
+        self.late_count.load(Relaxed)
+    }
+}
+
+/// Tracks event-time progress as `max_seen_ts - max_out_of_order_nanos`.
+///
+/// Items are passed through unchanged. Items whose timestamp is below the
+/// current watermark are considered late and are counted in the shared state.
+pub struct Watermark<T, F> {
+    ts_fn: F,
+    max_out_of_order_nanos: u64,
+    max_seen_ts: u64,
+    state: WatermarkState,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Pod + Send, F: Fn(&T) -> u64> Watermark<T, F> {
+    pub fn new(ts_fn: F, max_out_of_order_nanos: u64) -> Self {
+        Self {
+            ts_fn,
+            max_out_of_order_nanos,
+            max_seen_ts: 0,
+            state: WatermarkState::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns a handle to the shared watermark state.
+    pub fn state(&self) -> WatermarkState {
+        self.state.clone()
+    }
+}
+
+impl<T: Pod + Send, F: Fn(&T) -> u64> Stage<T, T> for Watermark<T, F> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &T, collector: &mut C)
+    where
+        C: OutputCollector<T>,
+    {
+        let ts = (self.ts_fn)(data);
+        let current = self.state.watermark.load(Relaxed);
+
+        if ts < current {
+            self.state.late_count.fetch_add(1, Relaxed);
+        } else if ts > self.max_seen_ts {
+            self.max_seen_ts = ts;
+            let next = ts.saturating_sub(self.max_out_of_order_nanos);
+            if next > current {
+                self.state.watermark.store(next, Release);
+            }
+        }
+
+        collector.push(data);
+    }
+}
+
+pub fn watermark<T: Pod + Send>(
+    ts_fn: impl Fn(&T) -> u64 + Send,
+    max_out_of_order_nanos: u64,
+) -> Watermark<T, impl Fn(&T) -> u64 + Send> {
+    Watermark::new(ts_fn, max_out_of_order_nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watermark_advances_in_order() {
+        let mut pipe = watermark(|ts: &u64| *ts, 10);
+        let state = pipe.state();
+        let mut out = Vec::new();
+
+        assert_eq!(state.current_watermark(), 0);
+
+        pipe.process(&5, &mut |x: &u64| out.push(*x));
+        assert_eq!(state.current_watermark(), 0);
+
+        pipe.process(&30, &mut |x: &u64| out.push(*x));
+        assert_eq!(state.current_watermark(), 20);
+
+        pipe.process(&50, &mut |x: &u64| out.push(*x));
+        assert_eq!(state.current_watermark(), 40);
+
+        assert_eq!(out, vec![5, 30, 50]);
+        assert_eq!(state.late_count(), 0);
+    }
+
+    #[test]
+    fn test_watermark_late_items() {
+        let mut pipe = watermark(|ts: &u64| *ts, 10);
+        let state = pipe.state();
+        let mut out = Vec::new();
+
+        pipe.process(&100, &mut |x: &u64| out.push(*x));
+        // Out of order but within tolerance
+        pipe.process(&95, &mut |x: &u64| out.push(*x));
+        // Behind the watermark
+        pipe.process(&80, &mut |x: &u64| out.push(*x));
+
+        assert_eq!(state.current_watermark(), 90);
+        assert_eq!(state.late_count(), 1);
+        assert_eq!(out, vec![100, 95, 80]);
+    }
+}
@@ -0,0 +1,110 @@
+use pprof::ProfilerGuardBuilder;
+use pprof::Report;
+use std::collections::HashMap;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+/// Sampling frequency of `RodaEngine::profile_worker` in Hz.
+const FREQUENCY: i32 = 1000;
+
+/// Errors returned by `RodaEngine::profile_worker`.
+#[derive(Debug)]
+pub enum ProfileError {
+    /// No worker thread was started with this id.
+    UnknownWorker(usize),
+    /// The sampling profiler failed.
+    Profiler(pprof::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::UnknownWorker(worker_id) => {
+                write!(f, "No worker thread with id {}", worker_id)
+            }
+            ProfileError::Profiler(e) => write!(f, "Profiler failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<pprof::Error> for ProfileError {
+    fn from(e: pprof::Error) -> Self {
+        ProfileError::Profiler(e)
+    }
+}
+
+/// A sampled call stack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTrace {
+    /// Function names, innermost first.
+    pub frames: Vec<String>,
+    /// Number of samples with this stack.
+    pub count: u32,
+}
+
+/// Call stacks sampled from a worker thread by `RodaEngine::profile_worker`.
+pub struct WorkerProfile {
+    pub samples: Vec<StackTrace>,
+    /// Innermost functions and their sample counts, most sampled first.
+    pub top_functions: Vec<(String, u32)>,
+    report: Report,
+}
+
+impl WorkerProfile {
+    /// Samples the whole process for `duration` and keeps the samples of `thread_name`.
+    pub(crate) fn record(thread_name: &str, duration: Duration) -> Result<Self, ProfileError> {
+        let guard = ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()?;
+        thread::sleep(duration);
+        let mut report = guard.report().build()?;
+        report
+            .data
+            .retain(|frames, _| frames.thread_name == thread_name);
+
+        let samples: Vec<StackTrace> = report
+            .data
+            .iter()
+            .map(|(frames, count)| StackTrace {
+                frames: frames
+                    .frames
+                    .iter()
+                    .flatten()
+                    .map(|symbol| symbol.name())
+                    .collect(),
+                count: *count as u32,
+            })
+            .collect();
+
+        let mut top: HashMap<&str, u32> = HashMap::new();
+        for sample in &samples {
+            if let Some(function) = sample.frames.first() {
+                *top.entry(function.as_str()).or_default() += sample.count;
+            }
+        }
+        let mut top_functions: Vec<(String, u32)> = top
+            .into_iter()
+            .map(|(function, count)| (function.to_string(), count))
+            .collect();
+        top_functions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Ok(Self {
+            samples,
+            top_functions,
+            report,
+        })
+    }
+
+    /// Renders the samples as a flamegraph SVG.
+    pub fn to_flamegraph_svg(&self) -> String {
+        let mut svg = Vec::new();
+        self.report
+            .flamegraph(&mut svg)
+            .expect("Failed to render flamegraph");
+        String::from_utf8(svg).expect("Flamegraph is not UTF-8")
+    }
+}
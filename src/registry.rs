@@ -0,0 +1,62 @@
+use std::sync::Weak;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A snapshot of the size of a store registered with `RodaEngine::register_store`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreMetadata {
+    pub name: &'static str,
+    /// Number of items appended so far.
+    pub count: usize,
+    /// Maximum number of items the store can hold.
+    pub capacity: usize,
+    /// Size of a single item in bytes.
+    pub element_size: usize,
+}
+
+impl StoreMetadata {
+    /// Returns the fraction of the capacity in use, between 0 and 1.
+    pub fn utilisation(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.count as f64 / self.capacity as f64
+    }
+}
+
+/// Memory reserved by the stores registered with a `RodaEngine`, from
+/// `RodaEngine::memory_snapshot`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineMemorySnapshot {
+    /// Name and reserved bytes of each registered store that is still alive, sorted by name.
+    pub stores: Vec<(String, usize)>,
+    pub total_bytes: usize,
+}
+
+/// A registry entry. It only holds a weak reference to the store's write index, so it
+/// does not keep the store alive.
+#[derive(Clone)]
+pub(crate) struct RegisteredStore {
+    pub(crate) write_index: Weak<AtomicUsize>,
+    pub(crate) capacity: usize,
+    pub(crate) element_size: usize,
+    /// Bytes reserved for the store at registration.
+    pub(crate) memory_usage: usize,
+}
+
+impl RegisteredStore {
+    /// Returns the current metadata, or `None` if the store has been dropped.
+    pub(crate) fn metadata(&self, name: &'static str) -> Option<StoreMetadata> {
+        let write_index = self.write_index.upgrade()?.load(Ordering::Acquire);
+        Some(StoreMetadata {
+            name,
+            count: write_index / self.element_size,
+            capacity: self.capacity,
+            element_size: self.element_size,
+        })
+    }
+
+    /// Returns the reserved bytes, or `None` if the store has been dropped.
+    pub(crate) fn memory_usage(&self) -> Option<usize> {
+        (self.write_index.strong_count() > 0).then_some(self.memory_usage)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::journal_store::{JournalStore, StoreJournalReader};
+use bytemuck::Pod;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Replays the items of a `JournalStore`, pacing them by their event timestamps.
+///
+/// Timestamps are in nanoseconds. The gap between two consecutive items is divided by the
+/// speedup factor: `1.0` replays in real time, `2.0` twice as fast, and `0.0` or
+/// `f64::INFINITY` as fast as possible. Pacing is relative to when the previous item was
+/// due, so a slow consumer catches up instead of drifting.
+pub struct ReplayEngine<T: Pod + Send, F> {
+    _store: JournalStore<T>,
+    reader: StoreJournalReader<T>,
+    ts_fn: F,
+    speedup: f64,
+    /// Timestamp and due time of the previously returned item.
+    last: Option<(u64, Instant)>,
+}
+
+impl<T: Pod + Send, F: Fn(&T) -> u64> ReplayEngine<T, F> {
+    pub fn new(store: JournalStore<T>, ts_fn: F, speedup: f64) -> Self {
+        assert!(speedup >= 0.0, "speedup must not be negative");
+        let reader = store.reader();
+        Self {
+            _store: store,
+            reader,
+            ts_fn,
+            speedup,
+            last: None,
+        }
+    }
+
+    /// Changes the speedup factor, taking effect from the next item.
+    pub fn set_speedup(&mut self, speedup: f64) {
+        assert!(speedup >= 0.0, "speedup must not be negative");
+        self.speedup = speedup;
+    }
+}
+
+impl<T: Pod + Send, F: Fn(&T) -> u64> Iterator for ReplayEngine<T, F> {
+    type Item = T;
+
+    /// Returns the next item once it is due, or `None` if the store has no more items.
+    ///
+    /// The first item is returned immediately. Items appended after `None` was returned
+    /// are picked up by later calls.
+    fn next(&mut self) -> Option<T> {
+        let item = self.reader.peek()?;
+        let ts = (self.ts_fn)(&item);
+
+        let due = match self.last {
+            Some((last_ts, last_due)) if self.speedup > 0.0 && self.speedup.is_finite() => {
+                let gap = ts.saturating_sub(last_ts) as f64 / self.speedup;
+                last_due + Duration::from_nanos(gap as u64)
+            }
+            _ => Instant::now(),
+        };
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+
+        self.reader.next();
+        self.last = Some((ts, due));
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JournalStoreOptions, RodaEngine};
+
+    fn store_with(engine: &RodaEngine, timestamps: &[u64]) -> JournalStore<u64> {
+        let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+            name: "replay_test".into(),
+            size: 16,
+            in_memory: true,
+        });
+        for ts in timestamps {
+            store.append(ts);
+        }
+        store
+    }
+
+    #[test]
+    fn test_replay_speedup() {
+        let engine = RodaEngine::new();
+        let store = store_with(&engine, &[1_000_000_000, 1_100_000_000]);
+        let mut replay = ReplayEngine::new(store, |ts: &u64| *ts, 2.0);
+
+        // Only the lower bound is checked, a loaded machine may oversleep
+        let start = Instant::now();
+        assert_eq!(replay.next(), Some(1_000_000_000));
+        assert_eq!(replay.next(), Some(1_100_000_000));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(50), "took {:?}", elapsed);
+        assert_eq!(replay.next(), None);
+    }
+
+    #[test]
+    fn test_replay_as_fast_as_possible() {
+        let engine = RodaEngine::new();
+        let timestamps: Vec<u64> = (0..10).map(|i| i * 1_000_000_000).collect();
+        let store = store_with(&engine, &timestamps);
+        let mut replay = ReplayEngine::new(store, |ts: &u64| *ts, f64::INFINITY);
+
+        let start = Instant::now();
+        let mut out = Vec::new();
+        while let Some(ts) = replay.next() {
+            out.push(ts);
+            replay.set_speedup(0.0);
+        }
+        assert_eq!(out, timestamps);
+        // Replaying in real time would take 9 seconds
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,130 @@
+use bytemuck::{Pod, Zeroable};
+
+/// A fixed-size ring buffer that keeps the last `N` pushed values without allocating.
+///
+/// It is `Pod`, so it can be embedded in stage state that is itself stored in a journal.
+/// The struct is packed to stay free of padding for any `T` and `N`, so values are copied
+/// out rather than borrowed.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct RingBuffer<T: Pod, const N: usize> {
+    /// Index of the oldest value.
+    head: u64,
+    len: u64,
+    data: [T; N],
+}
+
+unsafe impl<T: Pod, const N: usize> Zeroable for RingBuffer<T, N> {}
+unsafe impl<T: Pod, const N: usize> Pod for RingBuffer<T, N> {}
+
+impl<T: Pod, const N: usize> RingBuffer<T, N> {
+    const CAPACITY_CHECK: () = assert!(N > 0, "RingBuffer capacity must not be zero");
+
+    pub fn new() -> Self {
+        let () = Self::CAPACITY_CHECK;
+        Self::zeroed()
+    }
+
+    /// Appends a value, overwriting the oldest one if the buffer is full.
+    #[inline(always)]
+    pub fn push(&mut self, val: T) {
+        let () = Self::CAPACITY_CHECK;
+        let (head, len) = (self.head as usize, self.len as usize);
+        if len < N {
+            self.data[(head + len) % N] = val;
+            self.len += 1;
+        } else {
+            self.data[head] = val;
+            self.head = ((head + 1) % N) as u64;
+        }
+    }
+
+    /// Removes and returns the oldest value.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let head = self.head as usize;
+        let val = self.data[head];
+        self.head = ((head + 1) % N) as u64;
+        self.len -= 1;
+        Some(val)
+    }
+
+    /// Returns the `index`-th value, counting from the oldest.
+    #[inline(always)]
+    pub fn get(&self, index: usize) -> Option<T> {
+        (index < self.len()).then(|| self.data[(self.head as usize + index) % N])
+    }
+
+    /// Iterates over the values from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len()).map(|i| self.data[(self.head as usize + i) % N])
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+}
+
+impl<T: Pod, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_push_pop_order() {
+        let mut ring = RingBuffer::<u32, 4>::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.pop(), None);
+
+        for i in 1..=3 {
+            ring.push(i);
+        }
+        assert!(!ring.is_full());
+        assert_eq!(ring.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        ring.push(4);
+        ring.push(5);
+        assert!(ring.is_full());
+        assert_eq!(ring.iter().collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert_eq!(ring.get(0), Some(2));
+        assert_eq!(ring.get(3), Some(5));
+        assert_eq!(ring.get(4), None);
+
+        assert_eq!(ring.pop(), Some(2));
+        assert_eq!(ring.pop(), Some(3));
+        ring.push(6);
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.iter().collect::<Vec<_>>(), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn test_ring_buffer_is_pod_without_padding() {
+        type Ring = RingBuffer<u8, 3>;
+        assert_eq!(size_of::<Ring>(), 2 * size_of::<u64>() + 3);
+
+        let mut ring = Ring::new();
+        ring.push(1);
+        ring.push(2);
+        let bytes = bytemuck::bytes_of(&ring).to_vec();
+        assert_eq!(bytes.len(), size_of::<Ring>());
+
+        let copy: Ring = bytemuck::pod_read_unaligned(&bytes);
+        assert_eq!(copy.iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
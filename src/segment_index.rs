@@ -0,0 +1,138 @@
+use crate::journal_store::JournalStore;
+use bytemuck::Pod;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The time range covered by a journal segment file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentMeta {
+    pub segment_id: usize,
+    /// Timestamp of the first item in the segment.
+    pub first_ts: u64,
+    /// Timestamp of the last item in the segment.
+    pub last_ts: u64,
+    pub path: PathBuf,
+}
+
+/// Maps time ranges to the journal segment files holding them.
+///
+/// Segments are kept ordered by time and must not overlap each other, as is the case
+/// for segments that are written one after another.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentIndex {
+    segments: Vec<SegmentMeta>,
+}
+
+impl SegmentIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the index to `path`, one tab-separated segment per line.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut content = String::new();
+        for segment in &self.segments {
+            let segment_path = segment.path.to_str().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Segment path is not UTF-8")
+            })?;
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                segment.segment_id, segment.first_ts, segment.last_ts, segment_path
+            ));
+        }
+        std::fs::write(path, content)
+    }
+
+    /// Reads an index written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid segment entry: {}", line),
+            )
+        };
+        let mut segments = Vec::new();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut fields = line.splitn(4, '\t');
+            let mut next_number = || -> Option<u64> { fields.next()?.parse().ok() };
+            let (Some(segment_id), Some(first_ts), Some(last_ts)) =
+                (next_number(), next_number(), next_number())
+            else {
+                return Err(invalid(line));
+            };
+            let path = fields.next().ok_or_else(|| invalid(line))?;
+            segments.push(SegmentMeta {
+                segment_id: segment_id as usize,
+                first_ts,
+                last_ts,
+                path: path.into(),
+            });
+        }
+        let mut index = Self { segments };
+        index.sort();
+        Ok(index)
+    }
+
+    /// Adds a segment, or replaces the segment with the same path.
+    pub fn insert(&mut self, segment: SegmentMeta) {
+        match self.segments.iter_mut().find(|s| s.path == segment.path) {
+            Some(existing) => *existing = segment,
+            None => self.segments.push(segment),
+        }
+        self.sort();
+    }
+
+    /// Adds or updates the entry for the file of `store`, whose items must be in timestamp
+    /// order.
+    ///
+    /// A new entry gets the next free segment id. Does nothing if the store is in memory
+    /// or empty.
+    pub fn update_from_store<T: Pod + Send>(
+        &mut self,
+        store: &JournalStore<T>,
+        ts_fn: impl Fn(&T) -> u64,
+    ) {
+        let (Some(path), Some(first), Some(last)) =
+            (store.path(), store.items().first(), store.items().last())
+        else {
+            return;
+        };
+        let segment_id = match self.segments.iter().find(|s| &s.path == path) {
+            Some(existing) => existing.segment_id,
+            None => self
+                .segments
+                .iter()
+                .map(|s| s.segment_id + 1)
+                .max()
+                .unwrap_or(0),
+        };
+        self.insert(SegmentMeta {
+            segment_id,
+            first_ts: ts_fn(first),
+            last_ts: ts_fn(last),
+            path: path.clone(),
+        });
+    }
+
+    /// Returns the segments with items in the time range `start..end`.
+    pub fn segments_for_range(&self, start: u64, end: u64) -> &[SegmentMeta] {
+        if start >= end {
+            return &[];
+        }
+        let from = self.segments.partition_point(|s| s.last_ts < start);
+        let to = self
+            .segments
+            .partition_point(|s| s.first_ts < end)
+            .max(from);
+        &self.segments[from..to]
+    }
+
+    /// Returns all segments ordered by time.
+    pub fn segments(&self) -> &[SegmentMeta] {
+        &self.segments
+    }
+
+    fn sort(&mut self) {
+        self.segments.sort_by_key(|s| (s.first_ts, s.last_ts));
+    }
+}
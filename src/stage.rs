@@ -4,6 +4,10 @@ use std::marker::PhantomData;
 /// Represents a processing stage in the pipeline.
 ///
 /// A stage takes an input of type `In` and can produce zero or more outputs of type `Out`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a stage from `{In}` to `{Out}`",
+    label = "expected a stage from `{In}` to `{Out}`"
+)]
 pub trait Stage<In: Pod + Send, Out: Pod + Send> {
     /// Processes a single input item.
     fn process<C>(&mut self, data: &In, collector: &mut C)
@@ -15,6 +19,15 @@ pub trait Stage<In: Pod + Send, Out: Pod + Send> {
 pub trait OutputCollector<T> {
     /// Collects a single output item.
     fn push(&mut self, item: &T);
+
+    /// Returns how many items were pushed to this collector.
+    ///
+    /// The collectors passed by `StageEngine` and `pipe!` pipelines count the items pushed
+    /// during the current `process` call. Collectors that do not count, such as closures,
+    /// return `usize::MAX`.
+    fn count(&self) -> usize {
+        usize::MAX
+    }
 }
 
 impl<T, F> OutputCollector<T> for F
@@ -27,6 +40,13 @@ where
     }
 }
 
+/// A value returned from a closure stage that can be pushed to a collector.
+///
+/// Implemented for `T`, `&T`, `Option<T>` and `Option<&T>`.
+#[diagnostic::on_unimplemented(
+    message = "a closure returning `{Self}` cannot be a stage with output `{T}`",
+    label = "expected `{T}`, `&{T}`, `Option<{T}>` or `Option<&{T}>`"
+)]
 pub trait StageOutput<T> {
     fn push_to<C: OutputCollector<T>>(self, collector: &mut C);
 }
@@ -88,6 +108,7 @@ pub struct Pipeline<S1, S2, In, Mid, Out> {
 pub struct PipelineCollector<'a, S, C, T> {
     stage: &'a mut S,
     collector: &'a mut C,
+    count: usize,
     _phantom: PhantomData<T>,
 }
 
@@ -100,8 +121,14 @@ where
 {
     #[inline(always)]
     fn push(&mut self, item: &In) {
+        self.count += 1;
         self.stage.process(item, self.collector);
     }
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.count
+    }
 }
 
 impl<In, Mid, Out, S1, S2> Stage<In, Out> for Pipeline<S1, S2, In, Mid, Out>
@@ -120,6 +147,7 @@ where
         let mut pc = PipelineCollector {
             stage: &mut self.s2,
             collector,
+            count: 0,
             _phantom: PhantomData,
         };
         self.s1.process(data, &mut pc);
@@ -185,4 +213,37 @@ mod tests {
         p.process(&10u32, &mut |x: &u8| out.push(*x));
         assert_eq!(out, vec![10u8, 10u8]);
     }
+
+    #[test]
+    fn test_pipeline_collector_count() {
+        // Emits `data` copies of each item, but stops after the first two.
+        struct RepeatAtMostTwice {
+            counts: Vec<usize>,
+        }
+        impl Stage<u32, u32> for RepeatAtMostTwice {
+            fn process<C>(&mut self, data: &u32, collector: &mut C)
+            where
+                C: OutputCollector<u32>,
+            {
+                self.counts.push(collector.count());
+                for _ in 0..*data {
+                    if collector.count() >= 2 {
+                        break;
+                    }
+                    collector.push(data);
+                    self.counts.push(collector.count());
+                }
+            }
+        }
+
+        let mut p = RepeatAtMostTwice { counts: vec![] }.pipe(|x: &u32| Some(*x));
+        let mut out = Vec::new();
+        p.process(&5, &mut |x: &u32| out.push(*x));
+        p.process(&1, &mut |x: &u32| out.push(*x));
+
+        assert_eq!(out, vec![5, 5, 1]);
+        assert_eq!(p.s1.counts, vec![0, 1, 2, 0, 1]);
+        let closure = |_: &u32| {};
+        assert_eq!(OutputCollector::<u32>::count(&closure), usize::MAX);
+    }
 }
@@ -1,18 +1,174 @@
 use crate::components::Appendable;
-use crate::stage::Stage;
+use crate::stage::{OutputCollector, Stage};
 use crate::{JournalStore, JournalStoreOptions, RodaEngine, StoreJournalReader};
 use bytemuck::Pod;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Receives the messages of panicking stages and counts them.
+struct PanicHandler {
+    handler: Box<dyn Fn(String) + Send + Sync>,
+    count: AtomicU64,
+}
+
+impl PanicHandler {
+    fn handle(&self, payload: Box<dyn std::any::Any + Send>) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Unknown panic".to_string()
+        };
+        (self.handler)(message);
+    }
+}
+
+/// Item counts and timing of one stage, from `StageEngine::pipeline_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageReport {
+    pub name: String,
+    pub items_in: u64,
+    pub items_out: u64,
+    /// Input items per second since the stage was added.
+    pub throughput_per_sec: f64,
+    /// Average time spent processing an input item.
+    pub avg_latency_ns: f64,
+}
+
+/// A snapshot of the counters of all stages of a pipeline, in pipeline order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineReport {
+    pub stages: Vec<StageReport>,
+}
+
+/// Counters of a stage, updated by its worker with relaxed atomic operations.
+struct StageCounters {
+    name: String,
+    started: Instant,
+    items_in: AtomicU64,
+    items_out: AtomicU64,
+    busy_nanos: AtomicU64,
+}
+
+impl StageCounters {
+    fn new(name: String) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            started: Instant::now(),
+            items_in: AtomicU64::new(0),
+            items_out: AtomicU64::new(0),
+            busy_nanos: AtomicU64::new(0),
+        })
+    }
+
+    fn report(&self) -> StageReport {
+        let items_in = self.items_in.load(Ordering::Relaxed);
+        let busy_nanos = self.busy_nanos.load(Ordering::Relaxed);
+        StageReport {
+            name: self.name.clone(),
+            items_in,
+            items_out: self.items_out.load(Ordering::Relaxed),
+            throughput_per_sec: items_in as f64 / self.started.elapsed().as_secs_f64(),
+            avg_latency_ns: if items_in == 0 {
+                0.0
+            } else {
+                busy_nanos as f64 / items_in as f64
+            },
+        }
+    }
+}
+
+/// Appends the output of a stage to the next store, counting the items pushed during one
+/// `process` call.
+struct StoreCollector<'a, T: Pod + Send> {
+    store: &'a mut JournalStore<T>,
+    count: usize,
+}
+
+impl<'a, T: Pod + Send> StoreCollector<'a, T> {
+    fn new(store: &'a mut JournalStore<T>) -> Self {
+        Self { store, count: 0 }
+    }
+}
+
+impl<T: Pod + Send> OutputCollector<T> for StoreCollector<'_, T> {
+    #[inline(always)]
+    fn push(&mut self, item: &T) {
+        self.count += 1;
+        self.store.append(item);
+    }
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Wraps a stage to update its `StageCounters`.
+struct CountingStage<S> {
+    stage: S,
+    counters: Arc<StageCounters>,
+}
+
+struct CountingCollector<'a, C> {
+    collector: &'a mut C,
+    items_out: &'a AtomicU64,
+}
+
+impl<T, C: OutputCollector<T>> OutputCollector<T> for CountingCollector<'_, C> {
+    #[inline(always)]
+    fn push(&mut self, item: &T) {
+        self.items_out.fetch_add(1, Ordering::Relaxed);
+        self.collector.push(item);
+    }
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.collector.count()
+    }
+}
+
+impl<In: Pod + Send, Out: Pod + Send, S: Stage<In, Out>> Stage<In, Out> for CountingStage<S> {
+    #[inline(always)]
+    fn process<C>(&mut self, data: &In, collector: &mut C)
+    where
+        C: OutputCollector<Out>,
+    {
+        let start = Instant::now();
+        self.counters.items_in.fetch_add(1, Ordering::Relaxed);
+        self.stage.process(
+            data,
+            &mut CountingCollector {
+                collector,
+                items_out: &self.counters.items_out,
+            },
+        );
+        self.counters
+            .busy_nanos
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+}
 
 /// A threaded pipeline engine that grows by adding stages.
 /// Each stage runs in its own thread and communicates via JournalStore.
 pub struct StageEngine<In: Pod + Send + 'static, Out: Pod + Send + 'static> {
     engine: RodaEngine,
-    input_store: JournalStore<In>,
+    /// Engines running the stages before a split, shared with the other branch.
+    upstream: Vec<Arc<RodaEngine>>,
+    /// `None` for the second branch of a split, which only receives.
+    input_store: Option<JournalStore<In>>,
     output_reader: StoreJournalReader<Out>,
     stage_count: usize,
     default_capacity: usize,
+    panic_handler: Option<Arc<PanicHandler>>,
+    /// Whether stages added from now on update `stage_counters`.
+    report: bool,
+    stage_counters: Vec<Arc<StageCounters>>,
 }
 
 impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> StageEngine<In, Out> {
@@ -38,45 +194,222 @@ impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> StageEngine<In, Out> {
     >(
         mut self,
         capacity: usize,
-        mut stage: S,
+        stage: S,
     ) -> StageEngine<In, NextOut> {
         let stage_idx = self.stage_count;
         self.stage_count += 1;
 
-        // Use a leaked string for the store name as JournalStoreOptions requires &'static str.
-        // In a production long-running system, we would use a more robust name management,
-        // but for a pipeline that lasts the lifetime of the process, this is acceptable.
-        let name = Box::leak(format!("stage_{}", stage_idx).into_boxed_str());
+        let name = format!("stage_{}", stage_idx);
 
-        let mut next_store = self
+        let next_store = self
             .engine
             .new_journal_store::<NextOut>(JournalStoreOptions {
-                name,
+                name: name.clone().into(),
                 size: capacity,
                 in_memory: true,
             });
 
         let reader = self.output_reader;
         let next_reader = next_store.reader();
-
-        self.engine.run_worker(move || {
-            reader.handle_remaining(|data| {
-                stage.process(data, &mut |out: &NextOut| next_store.append(out));
-            }) > 0
-        });
+        let panic_handler = self.panic_handler.clone();
+        if self.report {
+            let counters = StageCounters::new(name);
+            self.stage_counters.push(counters.clone());
+            let stage = CountingStage { stage, counters };
+            Self::run_stage(&mut self.engine, panic_handler, reader, next_store, stage);
+        } else {
+            Self::run_stage(&mut self.engine, panic_handler, reader, next_store, stage);
+        }
 
         StageEngine {
             engine: self.engine,
+            upstream: self.upstream,
             input_store: self.input_store,
             output_reader: next_reader,
             stage_count: self.stage_count,
             default_capacity: self.default_capacity,
+            panic_handler: self.panic_handler,
+            report: self.report,
+            stage_counters: self.stage_counters,
+        }
+    }
+
+    /// Spawns a worker that feeds the items of `reader` to `stage` and appends its output to
+    /// `next_store`.
+    fn run_stage<NextOut: Pod + Send + 'static, S: Stage<Out, NextOut> + Send + 'static>(
+        engine: &mut RodaEngine,
+        panic_handler: Option<Arc<PanicHandler>>,
+        reader: StoreJournalReader<Out>,
+        mut next_store: JournalStore<NextOut>,
+        mut stage: S,
+    ) {
+        match panic_handler {
+            None => engine.run_worker(move || {
+                reader.handle_remaining(|data| {
+                    stage.process(data, &mut StoreCollector::new(&mut next_store));
+                }) > 0
+            }),
+            Some(panic_handler) => engine.run_worker(move || {
+                // The cursor is advanced before processing, so a panicking item is skipped
+                let mut did_work = false;
+                while reader.next() {
+                    did_work = true;
+                    reader.with(|data| {
+                        let result = catch_unwind(AssertUnwindSafe(|| {
+                            stage.process(data, &mut StoreCollector::new(&mut next_store));
+                        }));
+                        if let Err(payload) = result {
+                            panic_handler.handle(payload);
+                        }
+                    });
+                }
+                did_work
+            }),
+        }
+    }
+
+    /// Catches panics of the stages added after this call instead of stopping the pipeline.
+    ///
+    /// The item that caused the panic is skipped, `handler` is called with the panic message
+    /// and the stage keeps processing the following items.
+    pub fn with_error_handler(mut self, handler: impl Fn(String) + Send + Sync + 'static) -> Self {
+        self.panic_handler = Some(Arc::new(PanicHandler {
+            handler: Box::new(handler),
+            count: AtomicU64::new(0),
+        }));
+        self
+    }
+
+    /// Measures item counts and timing of the stages added after this call, see
+    /// `pipeline_report`.
+    ///
+    /// Off by default, as it adds two clock reads and a few atomic operations per item.
+    pub fn with_report(mut self) -> Self {
+        self.report = true;
+        self
+    }
+
+    /// Returns the number of stage panics caught by the error handler.
+    pub fn panic_count(&self) -> u64 {
+        self.panic_handler
+            .as_ref()
+            .map_or(0, |h| h.count.load(Ordering::Relaxed))
+    }
+
+    /// Splits the pipeline into two branches.
+    ///
+    /// Items for which `predicate` returns true go to the first branch, all others to the
+    /// second. A new thread is spawned to route the items. Stages can be added to each
+    /// branch independently. The input store stays single-writer: only the first branch
+    /// accepts input, and `send` on the second branch panics.
+    pub fn split_at(
+        mut self,
+        predicate: impl Fn(&Out) -> bool + Send + 'static,
+    ) -> (StageEngine<In, Out>, StageEngine<In, Out>) {
+        let stage_idx = self.stage_count;
+        self.stage_count += 1;
+
+        let options = |branch: &str| JournalStoreOptions {
+            name: format!("stage_{}_{}", stage_idx, branch).into(),
+            size: self.default_capacity,
+            in_memory: true,
+        };
+        let mut matched_store = self.engine.new_journal_store::<Out>(options("matched"));
+        let mut rest_store = self.engine.new_journal_store::<Out>(options("rest"));
+        let matched_reader = matched_store.reader();
+        let rest_reader = rest_store.reader();
+
+        let reader = self.output_reader;
+        let mut matched_counters = self.stage_counters.clone();
+        let mut rest_counters = self.stage_counters;
+        if self.report {
+            // Each branch reports the items routed to it as the output of the split
+            let matched = StageCounters::new(format!("stage_{}_matched", stage_idx));
+            let rest = StageCounters::new(format!("stage_{}_rest", stage_idx));
+            matched_counters.push(matched.clone());
+            rest_counters.push(rest.clone());
+            self.engine.run_worker(move || {
+                reader.handle_remaining(|data| {
+                    let start = Instant::now();
+                    let counters = if predicate(data) {
+                        matched_store.append(data);
+                        &matched
+                    } else {
+                        rest_store.append(data);
+                        &rest
+                    };
+                    counters.items_out.fetch_add(1, Ordering::Relaxed);
+                    let busy_nanos = start.elapsed().as_nanos() as u64;
+                    for counters in [&matched, &rest] {
+                        counters.items_in.fetch_add(1, Ordering::Relaxed);
+                        counters.busy_nanos.fetch_add(busy_nanos, Ordering::Relaxed);
+                    }
+                }) > 0
+            });
+        } else {
+            self.engine.run_worker(move || {
+                reader.handle_remaining(|data| {
+                    if predicate(data) {
+                        matched_store.append(data);
+                    } else {
+                        rest_store.append(data);
+                    }
+                }) > 0
+            });
         }
+
+        let matched_engine = self.engine.new_sibling();
+        let rest_engine = self.engine.new_sibling();
+        let mut upstream = self.upstream;
+        upstream.push(Arc::new(self.engine));
+
+        (
+            StageEngine {
+                engine: matched_engine,
+                upstream: upstream.clone(),
+                input_store: self.input_store,
+                output_reader: matched_reader,
+                stage_count: self.stage_count,
+                default_capacity: self.default_capacity,
+                panic_handler: self.panic_handler.clone(),
+                report: self.report,
+                stage_counters: matched_counters,
+            },
+            StageEngine {
+                engine: rest_engine,
+                upstream,
+                input_store: None,
+                output_reader: rest_reader,
+                stage_count: self.stage_count,
+                default_capacity: self.default_capacity,
+                panic_handler: self.panic_handler,
+                report: self.report,
+                stage_counters: rest_counters,
+            },
+        )
+    }
+
+    fn input_store(&mut self) -> &mut JournalStore<In> {
+        self.input_store
+            .as_mut()
+            .expect("The second branch of a split pipeline has no input, send through the first")
     }
 
     /// Sends data into the start of the pipeline.
     pub fn send(&mut self, data: &In) {
-        self.input_store.append(data);
+        self.input_store().append(data);
+    }
+
+    /// Sends all items into the start of the pipeline at once.
+    ///
+    /// The first stage is notified once for the whole batch instead of once per item.
+    pub fn send_batch(&mut self, items: &[In]) {
+        self.input_store().bulk_append(items);
+    }
+
+    fn is_any_worker_panicked(&self) -> bool {
+        self.engine.is_any_worker_panicked()
+            || self.upstream.iter().any(|e| e.is_any_worker_panicked())
     }
 
     /// Receives data from the end of the pipeline.
@@ -87,7 +420,7 @@ impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> StageEngine<In, Out> {
             if let Some(data) = self.try_receive() {
                 return Some(data);
             }
-            if self.engine.is_any_worker_panicked() {
+            if self.is_any_worker_panicked() {
                 panic!("Worker panicked, pipeline is broken");
             }
             thread::yield_now();
@@ -102,17 +435,123 @@ impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> StageEngine<In, Out> {
         None
     }
 
+    /// Receives up to `max` items that are already available at the end of the pipeline.
+    pub fn try_receive_batch(&self, max: usize) -> Vec<Out> {
+        let mut batch = Vec::new();
+        self.output_reader
+            .handle_remaining_up_to(max, |data| batch.push(*data));
+        batch
+    }
+
+    /// Receives `n` items from the end of the pipeline.
+    ///
+    /// This will block until `n` items are available or the timeout expires, in which case
+    /// the items received so far are returned.
+    pub fn receive_batch(&self, n: usize, timeout: Duration) -> Vec<Out> {
+        let start = Instant::now();
+        let available = self
+            .output_reader
+            .size()
+            .saturating_sub(self.output_reader.get_index());
+        let mut batch = Vec::with_capacity(n.min(available));
+        while batch.len() < n {
+            let received = self
+                .output_reader
+                .handle_remaining_up_to(n - batch.len(), |data| batch.push(*data));
+            if received > 0 {
+                continue;
+            }
+            if start.elapsed() > timeout {
+                break;
+            }
+            if self.is_any_worker_panicked() {
+                panic!("Worker panicked, pipeline is broken");
+            }
+            thread::yield_now();
+        }
+        batch
+    }
+
+    /// Returns an iterator that receives the items already available at the end of the
+    /// pipeline, ending when there are none left.
+    pub fn output_iter(&self) -> OutputIter<'_, In, Out> {
+        OutputIter { engine: self }
+    }
+
+    /// Returns an iterator that receives items from the end of the pipeline, waiting up to
+    /// `timeout` for each one and ending when none arrives in time.
+    pub fn blocking_output_iter(&self, timeout: Duration) -> impl Iterator<Item = Out> + '_ {
+        std::iter::from_fn(move || self.receive_timeout(timeout))
+    }
+
+    /// Receives one item like `receive`, giving up after `timeout`.
+    fn receive_timeout(&self, timeout: Duration) -> Option<Out> {
+        let start = Instant::now();
+        loop {
+            if let Some(data) = self.try_receive() {
+                return Some(data);
+            }
+            if start.elapsed() > timeout {
+                return None;
+            }
+            if self.is_any_worker_panicked() {
+                panic!("Worker panicked, pipeline is broken");
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Receives all items that are already available at the end of the pipeline.
+    pub fn drain_available(&self) -> Vec<Out> {
+        let mut items = Vec::new();
+        self.output_reader
+            .handle_remaining(|data| items.push(*data));
+        items
+    }
+
+    /// Waits for the pipeline to become idle like `await_idle`, then returns the remaining
+    /// output and stops the engine.
+    pub fn drain_and_close(self, timeout: Duration) -> Vec<Out> {
+        self.await_idle(timeout);
+        self.drain_available()
+    }
+
     /// Returns the number of items in the output store.
     pub fn output_size(&self) -> usize {
         self.output_reader.size()
     }
 
+    /// Returns the item counts and timing of every stage added after `with_report`,
+    /// including the stages before a split, in pipeline order.
+    ///
+    /// After a split, each branch reports the split as a stage whose output is the items
+    /// routed to that branch.
+    pub fn pipeline_report(&self) -> PipelineReport {
+        PipelineReport {
+            stages: self.stage_counters.iter().map(|c| c.report()).collect(),
+        }
+    }
+
     /// Waits for all workers to finish processing.
     pub fn await_idle(&self, timeout: Duration) {
         self.engine.await_idle(timeout);
     }
 }
 
+/// An iterator over the items available at the end of a pipeline, see
+/// `StageEngine::output_iter`.
+pub struct OutputIter<'a, In: Pod + Send + 'static, Out: Pod + Send + 'static> {
+    engine: &'a StageEngine<In, Out>,
+}
+
+impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> Iterator for OutputIter<'_, In, Out> {
+    type Item = Out;
+
+    fn next(&mut self) -> Option<Out> {
+        self.engine.try_receive()
+    }
+}
+
 impl<In: Pod + Send + 'static, Out: Pod + Send + 'static> Appendable<In> for StageEngine<In, Out> {
     fn append(&mut self, state: &In) {
         self.send(state);
@@ -136,7 +575,7 @@ impl<T: Pod + Send + 'static> StageEngine<T, T> {
     pub fn with_capacity(capacity: usize) -> Self {
         let engine = RodaEngine::new();
         let input_store = engine.new_journal_store(JournalStoreOptions {
-            name: "input",
+            name: "input".into(),
             size: capacity,
             in_memory: true,
         });
@@ -144,10 +583,14 @@ impl<T: Pod + Send + 'static> StageEngine<T, T> {
 
         Self {
             engine,
-            input_store,
+            upstream: vec![],
+            input_store: Some(input_store),
             output_reader,
             stage_count: 0,
             default_capacity: capacity,
+            panic_handler: None,
+            report: false,
+            stage_counters: vec![],
         }
     }
 }
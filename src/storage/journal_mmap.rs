@@ -2,8 +2,8 @@ use bytemuck::Pod;
 use memmap2::{MmapMut, MmapOptions};
 use std::fs::OpenOptions;
 use std::path::PathBuf;
-use std::sync::Arc;
 use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Weak};
 
 /// A memory-mapped buffer optimized for sequential, append-only operations.
 ///
@@ -14,6 +14,7 @@ pub(crate) struct JournalMmap {
     len: usize,
     write_index: Arc<AtomicUsize>,
     read_only: bool,
+    path: Option<PathBuf>,
 }
 
 impl JournalMmap {
@@ -41,6 +42,7 @@ impl JournalMmap {
             len,
             write_index: Arc::new(Default::default()),
             read_only: false,
+            path,
         })
     }
 
@@ -58,9 +60,69 @@ impl JournalMmap {
             len,
             write_index: Arc::new(Default::default()),
             read_only: false,
+            path: Some(path),
         })
     }
 
+    /// GROW: Remaps the buffer with a larger size, keeping the written data.
+    ///
+    /// File-backed buffers are extended on disk and mapped again, in-memory buffers are
+    /// copied into a new anonymous mapping. The write index is kept, so registry entries
+    /// stay valid. Readers would keep reading the old mapping, so this fails while any exist.
+    pub(crate) fn extend(&mut self, new_len: usize) -> Result<(), std::io::Error> {
+        assert!(!self.read_only, "Cannot mutate read-only buffer");
+        if new_len <= self.len {
+            return Ok(());
+        }
+        self.ensure_no_readers()?;
+
+        let write_index = self.get_write_index();
+        let mut mmap = if let Some(p) = &self.path {
+            self._mmap.flush()?;
+            let file = OpenOptions::new().read(true).write(true).open(p)?;
+            file.set_len(new_len as u64)?;
+            unsafe { MmapOptions::new().huge(Some(21)).map_mut(&file)? }
+        } else {
+            let mut mmap = MmapOptions::new().len(new_len).map_anon()?;
+            mmap[..write_index].copy_from_slice(&self._mmap[..write_index]);
+            mmap
+        };
+
+        self.ptr = mmap.as_mut_ptr();
+        self.len = mmap.len();
+        self._mmap = Arc::new(mmap);
+        Ok(())
+    }
+
+    /// Takes over the mapping, path and written data of `other`, keeping the write index of
+    /// this buffer so registry entries stay valid.
+    ///
+    /// Check `ensure_no_readers` first, readers would keep reading the old mapping.
+    pub(crate) fn replace_with(&mut self, other: JournalMmap) {
+        assert!(!self.read_only, "Cannot mutate read-only buffer");
+        let write_index = other.get_write_index();
+        self._mmap = other._mmap;
+        self.ptr = other.ptr;
+        self.len = other.len;
+        self.path = other.path;
+        self.write_index
+            .store(write_index, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Fails with `ResourceBusy` if readers of this buffer exist.
+    ///
+    /// Only readers share the mapping. Registry entries hold a weak write index, which
+    /// health checks upgrade while polling, so that count is not used here.
+    pub(crate) fn ensure_no_readers(&self) -> Result<(), std::io::Error> {
+        if Arc::strong_count(&self._mmap) > 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::ResourceBusy,
+                "Cannot remap a buffer while readers exist",
+            ));
+        }
+        Ok(())
+    }
+
     // --- Bytemuck Methods ---
 
     /// Casts bytes at offset to a reference of T.
@@ -130,6 +192,53 @@ impl JournalMmap {
             .store(end, std::sync::atomic::Ordering::Release);
     }
 
+    /// Appends all items with a single update of the write index.
+    ///
+    /// # Panics
+    /// Panics if the items do not fit.
+    #[inline(always)]
+    pub(crate) fn append_slice<T: Pod>(&mut self, items: &[T]) {
+        assert!(!self.read_only, "Cannot mutate read-only buffer");
+        let current_pos = self.write_index.load(std::sync::atomic::Ordering::Relaxed);
+        let bytes: &[u8] = bytemuck::cast_slice(items);
+        let end = current_pos + bytes.len();
+
+        assert!(end <= self.len, "Journal is full. Cannot append more data.");
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.ptr.add(current_pos), bytes.len());
+        }
+
+        self.write_index
+            .store(end, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Moves the write position, e.g. to resume appending to a loaded file.
+    pub(crate) fn set_write_index(&mut self, index: usize) {
+        assert!(!self.read_only, "Cannot mutate read-only buffer");
+        assert!(index <= self.len, "Write index past the end of the buffer");
+        self.write_index
+            .store(index, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Synchronously writes the given range of a file-backed buffer to disk (`msync`).
+    pub(crate) fn flush_range(&self, offset: usize, len: usize) -> Result<(), std::io::Error> {
+        self._mmap.flush_range(offset, len)
+    }
+
+    /// Returns the path of a file-backed buffer.
+    pub(crate) fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    /// Moves the file of a file-backed buffer to `path`, replacing any file there.
+    pub(crate) fn rename(&mut self, path: PathBuf) -> Result<(), std::io::Error> {
+        let current = self.path.as_ref().expect("Cannot rename in-memory buffer");
+        std::fs::rename(current, &path)?;
+        self.path = Some(path);
+        Ok(())
+    }
+
     #[inline(always)]
     pub(crate) fn get_write_index(&self) -> usize {
         self.write_index.load(std::sync::atomic::Ordering::Acquire)
@@ -140,6 +249,11 @@ impl JournalMmap {
         self.len
     }
 
+    /// Returns a handle to the write index that does not keep the buffer alive.
+    pub(crate) fn write_index_weak(&self) -> Weak<AtomicUsize> {
+        Arc::downgrade(&self.write_index)
+    }
+
     #[inline(always)]
     pub(crate) fn reader(&self) -> JournalMmap {
         JournalMmap {
@@ -148,6 +262,7 @@ impl JournalMmap {
             len: self.len,
             write_index: self.write_index.clone(),
             read_only: true,
+            path: self.path.clone(),
         }
     }
 }
@@ -158,6 +273,7 @@ unsafe impl Send for JournalMmap {}
 mod tests {
     use super::*;
     use bytemuck::Zeroable;
+    use std::sync::atomic::Ordering;
     use std::thread;
     use std::time::Duration;
 
@@ -262,6 +378,49 @@ mod tests {
         reader.append(&1u32);
     }
 
+    #[test]
+    fn test_extend_in_memory() {
+        let mut journal = JournalMmap::new(None, 8).unwrap();
+        let write_index = journal.write_index_weak();
+        journal.append(&1u32);
+        journal.extend(16).unwrap();
+        assert_eq!(journal.len(), 16);
+
+        journal.append(&2u32);
+        journal.append(&3u32);
+        journal.append(&4u32);
+        assert_eq!(journal.read_window::<u32>(0, 4), &[1, 2, 3, 4]);
+
+        // The write index is the same one handed out before the extension
+        assert_eq!(write_index.upgrade().unwrap().load(Ordering::Acquire), 16);
+    }
+
+    #[test]
+    fn test_extend_fails_while_readers_exist() {
+        let mut journal = JournalMmap::new(None, 8).unwrap();
+        let reader = journal.reader();
+        journal.append(&1u32);
+
+        let err = journal.extend(16).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+        assert_eq!(journal.len(), 8);
+
+        drop(reader);
+        journal.extend(16).unwrap();
+        assert_eq!(journal.len(), 16);
+    }
+
+    #[test]
+    fn test_extend_ignores_registry_handles() {
+        let mut journal = JournalMmap::new(None, 8).unwrap();
+        let write_index = journal.write_index_weak();
+        let upgraded = write_index.upgrade().unwrap();
+
+        journal.extend(16).unwrap();
+        assert_eq!(journal.len(), 16);
+        drop(upgraded);
+    }
+
     #[test]
     fn test_file_backed() {
         let path = std::env::temp_dir().join(format!("test_journal_{}.mmap", std::process::id()));
@@ -0,0 +1,263 @@
+//! Utilities for testing and benchmarking pipeline stages.
+use crate::measure::{LatencyMeasurer, LatencyStats};
+use crate::stage::Stage;
+use bytemuck::Pod;
+#[cfg(any(test, feature = "testing"))]
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::marker::PhantomData;
+use std::sync::Arc;
+#[cfg(any(test, feature = "testing"))]
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Results of a `PipelineBenchmarkHarness` run.
+#[derive(Debug, Clone, Default)]
+pub struct BenchmarkResults {
+    /// Number of measured input items.
+    pub items: usize,
+    /// Number of output items produced by the measured items.
+    pub outputs: usize,
+    /// Wall-clock time spent on the measured items.
+    pub elapsed: Duration,
+    /// Measured input items per second, or 0 if no time was measured.
+    pub throughput: f64,
+    /// Per-item processing latency.
+    pub latency: LatencyStats,
+}
+
+/// Measures throughput and per-item latency of a single `Stage`.
+///
+/// The harness is single-threaded and deterministic: the stage is driven directly,
+/// without a `StageEngine` or any intermediate stores.
+pub struct PipelineBenchmarkHarness<In: Pod + Send, Out: Pod + Send> {
+    _phantom: PhantomData<(In, Out)>,
+}
+
+impl<In: Pod + Send, Out: Pod + Send> PipelineBenchmarkHarness<In, Out> {
+    /// Runs `warmup` items (cycling through `inputs`) without measuring, then measures
+    /// one pass over all `inputs`.
+    pub fn run(mut stage: impl Stage<In, Out>, inputs: &[In], warmup: usize) -> BenchmarkResults {
+        if !inputs.is_empty() {
+            for data in inputs.iter().cycle().take(warmup) {
+                stage.process(data, &mut |out: &Out| {
+                    black_box(out);
+                });
+            }
+        }
+
+        let mut measurer = LatencyMeasurer::new(1);
+        let mut outputs = 0;
+        let start = Instant::now();
+        for data in inputs {
+            let _guard = measurer.measure_with_guard();
+            stage.process(data, &mut |out: &Out| {
+                black_box(out);
+                outputs += 1;
+            });
+        }
+        let elapsed = start.elapsed();
+        let throughput = if elapsed.is_zero() {
+            0.0
+        } else {
+            inputs.len() as f64 / elapsed.as_secs_f64()
+        };
+
+        BenchmarkResults {
+            items: inputs.len(),
+            outputs,
+            elapsed,
+            throughput,
+            latency: measurer.get_stats(),
+        }
+    }
+}
+
+/// Runs `stages` on every input in order on the calling thread and returns the outputs.
+///
+/// Unlike a `StageEngine`, nothing runs in the background, so the outputs are complete
+/// when this returns and tests do not need to wait for workers.
+pub fn run_pipeline_sync<In: Pod + Send, Out: Pod + Send>(
+    mut stages: impl Stage<In, Out>,
+    inputs: &[In],
+) -> Vec<Out> {
+    let mut outputs = Vec::new();
+    for data in inputs {
+        stages.process(data, &mut |out: &Out| outputs.push(*out));
+    }
+    outputs
+}
+
+/// Returns true if `run_pipeline_sync` produces exactly `expected`.
+pub fn run_pipeline_sync_checked<In: Pod + Send, Out: Pod + Send + PartialEq>(
+    stages: impl Stage<In, Out>,
+    inputs: &[In],
+    expected: &[Out],
+) -> bool {
+    run_pipeline_sync(stages, inputs) == expected
+}
+
+/// A manually advanced clock for testing time-dependent stages without sleeping.
+///
+/// Clones share the same time, so a clone can be moved into a stage while the test
+/// advances the original.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start_nanos: u64) -> Self {
+        Self {
+            nanos: Arc::new(AtomicU64::new(start_nanos)),
+        }
+    }
+
+    /// Returns the current time in nanoseconds.
+    pub fn now(&self) -> u64 {
+        self.nanos.load(Ordering::Acquire)
+    }
+
+    /// Moves the clock forward by `nanos`.
+    pub fn advance(&self, nanos: u64) {
+        self.nanos.fetch_add(nanos, Ordering::AcqRel);
+    }
+
+    /// Sets the clock to `nanos`.
+    pub fn set(&self, nanos: u64) {
+        self.nanos.store(nanos, Ordering::Release);
+    }
+
+    /// Returns a clock function for stages that accept one, such as
+    /// `timestamp_inject_with_clock`.
+    pub fn as_fn(&self) -> impl Fn() -> u64 + Send + Sync + Clone + 'static {
+        let clock = self.clone();
+        move || clock.now()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+thread_local! {
+    static MOCK_CLOCK: RefCell<Option<MockClock>> = const { RefCell::new(None) };
+}
+
+/// Number of `with_mock_clock` calls in progress on any thread, so the real clock does not
+/// pay for the thread-local lookup outside of tests.
+#[cfg(any(test, feature = "testing"))]
+static MOCK_CLOCKS_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `f` with `clock` as the time source of `timestamp_nanos` on the current thread.
+///
+/// Only stages driven on the calling thread see the mock clock; stages running on
+/// `StageEngine` workers need a clock passed in explicitly, see `MockClock::as_fn`.
+///
+/// Requires the `testing` feature, so release builds never check for a mock clock.
+#[cfg(any(test, feature = "testing"))]
+pub fn with_mock_clock<T>(clock: MockClock, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<MockClock>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MOCK_CLOCK.with(|current| *current.borrow_mut() = self.0.take());
+            MOCK_CLOCKS_ACTIVE.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    MOCK_CLOCKS_ACTIVE.fetch_add(1, Ordering::Acquire);
+    let _restore = Restore(MOCK_CLOCK.with(|current| current.borrow_mut().replace(clock)));
+    f()
+}
+
+/// Returns the time of the mock clock installed by `with_mock_clock` on this thread.
+#[cfg(any(test, feature = "testing"))]
+#[inline(always)]
+pub(crate) fn mock_now() -> Option<u64> {
+    if MOCK_CLOCKS_ACTIVE.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    MOCK_CLOCK.with(|current| current.borrow().as_ref().map(MockClock::now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harness_counts_items_and_outputs() {
+        let inputs: Vec<u32> = (0..1000).collect();
+        let results = PipelineBenchmarkHarness::run(
+            |x: &u32| x.is_multiple_of(2).then_some(*x as u64),
+            &inputs,
+            100,
+        );
+
+        assert_eq!(results.items, 1000);
+        assert_eq!(results.outputs, 500);
+        assert_eq!(results.latency.count, 1000);
+        assert!(results.throughput > 0.0);
+    }
+
+    #[test]
+    fn test_harness_empty_inputs() {
+        let results = PipelineBenchmarkHarness::run(|x: &u32| Some(*x), &[], 10);
+        assert_eq!(results.items, 0);
+        assert_eq!(results.outputs, 0);
+        assert_eq!(results.latency.count, 0);
+        assert!(results.throughput.is_finite());
+    }
+
+    #[test]
+    fn test_run_pipeline_sync() {
+        use crate::pipe;
+        use crate::pipe::{dedup_by, delta, stateful};
+
+        let inputs = [1u32, 1, 2, 2, 5, 3];
+        assert_eq!(
+            run_pipeline_sync(dedup_by(|_: &u32| 0), &inputs),
+            vec![1, 2, 5, 3]
+        );
+        assert!(run_pipeline_sync_checked(
+            delta(
+                |_: &u32| 0,
+                |curr, prev| prev.map(|p| *curr as i64 - p as i64)
+            ),
+            &inputs,
+            &[0, 1, 0, 3, -2],
+        ));
+        assert!(!run_pipeline_sync_checked(
+            pipe![dedup_by(|_: &u32| 0), |x: &u32| Some(*x * 10)],
+            &inputs,
+            &[10, 20, 50],
+        ));
+        assert_eq!(
+            run_pipeline_sync(
+                stateful(
+                    |x: &u32| x % 2,
+                    |x: &u32| *x,
+                    |sum: &mut u32, x: &u32| *sum += x
+                ),
+                &inputs,
+            ),
+            vec![1, 2, 2, 4, 7, 10]
+        );
+    }
+
+    #[test]
+    fn test_mock_clock_scoped_to_closure() {
+        let clock = MockClock::new(100);
+        assert_eq!(mock_now(), None);
+
+        let seen = with_mock_clock(clock.clone(), || {
+            clock.advance(50);
+            let outer = mock_now();
+            let inner = with_mock_clock(MockClock::new(7), mock_now);
+            (outer, inner, mock_now())
+        });
+        assert_eq!(seen, (Some(150), Some(7), Some(150)));
+        assert_eq!(mock_now(), None);
+
+        clock.set(0);
+        assert_eq!(clock.as_fn()(), 0);
+    }
+}
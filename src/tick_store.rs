@@ -0,0 +1,63 @@
+use crate::components::Appendable;
+use crate::journal_store::{JournalStore, StoreJournalReader};
+use bytemuck::Pod;
+
+/// A `JournalStore` of items ordered by timestamp, supporting time lookups by binary search.
+///
+/// Items must be appended in non-decreasing timestamp order.
+pub struct TickStore<State: Pod + Send> {
+    store: JournalStore<State>,
+    ts_fn: fn(&State) -> u64,
+}
+
+impl<State: Pod + Send> TickStore<State> {
+    pub fn new(store: JournalStore<State>, ts_fn: fn(&State) -> u64) -> Self {
+        Self { store, ts_fn }
+    }
+
+    /// Appends an item to the store.
+    ///
+    /// # Panics
+    /// Panics if the item is older than the last appended item.
+    pub fn append(&mut self, state: &State) {
+        if let Some(last) = self.store.last_appended() {
+            let (last_ts, ts) = ((self.ts_fn)(last), (self.ts_fn)(state));
+            assert!(
+                ts >= last_ts,
+                "Tick out of order. Last timestamp: {}, new timestamp: {}",
+                last_ts,
+                ts
+            );
+        }
+        self.store.append(state);
+    }
+
+    /// Returns the most recent item with a timestamp at or before `ts`.
+    pub fn get_at_time(&self, ts: u64) -> Option<State> {
+        let items = self.store.items();
+        let end = items.partition_point(|s| (self.ts_fn)(s) <= ts);
+        end.checked_sub(1).map(|i| items[i])
+    }
+
+    /// Returns the items with a timestamp in `start..end`.
+    pub fn range_by_time(&self, start: u64, end: u64) -> &[State] {
+        let items = self.store.items();
+        let from = items.partition_point(|s| (self.ts_fn)(s) < start);
+        let to = items.partition_point(|s| (self.ts_fn)(s) < end).max(from);
+        &items[from..to]
+    }
+
+    pub fn reader(&self) -> StoreJournalReader<State> {
+        self.store.reader()
+    }
+
+    pub fn size(&self) -> usize {
+        self.store.size()
+    }
+}
+
+impl<State: Pod + Send> Appendable<State> for TickStore<State> {
+    fn append(&mut self, state: &State) {
+        self.append(state);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::engine::RodaEngine;
+use crate::journal_store::{JournalStore, JournalStoreOptions, StoreJournalReader};
+use bytemuck::Pod;
+
+/// A chain of stores where each layer transforms the items of the layer above it.
+///
+/// Items are sent into the top store, and every layer runs a worker that moves the items
+/// of the previous store into its own store (raw → filtered → aggregated → ...).
+/// The waterfall owns all stores and workers. On drop, the workers are stopped first
+/// and the stores are released afterwards.
+pub struct Waterfall<State: Pod + Send + 'static> {
+    // Declared first so that the workers are stopped before the stores are dropped
+    engine: RodaEngine,
+    input: JournalStore<State>,
+    output_reader: StoreJournalReader<State>,
+    layer_count: usize,
+}
+
+/// A layer of a `Waterfall`, reading from the store above it and writing to its own store.
+///
+/// The layer starts moving items once `run_transform` is called.
+pub struct WaterfallLayer<'a, State: Pod + Send + 'static> {
+    waterfall: &'a mut Waterfall<State>,
+    input: StoreJournalReader<State>,
+    output: JournalStore<State>,
+}
+
+impl<State: Pod + Send + 'static> Waterfall<State> {
+    /// Creates a waterfall with a top store configured by `options`.
+    ///
+    /// Its workers share the operation counters of `engine`, so `engine.await_idle` also
+    /// waits for the waterfall.
+    pub fn new(engine: &RodaEngine, options: JournalStoreOptions) -> Self {
+        let engine = engine.new_sibling();
+        let input = engine.new_journal_store(options);
+        let output_reader = input.reader();
+        Self {
+            engine,
+            input,
+            output_reader,
+            layer_count: 0,
+        }
+    }
+
+    /// Adds a layer below the current bottom of the waterfall, storing its items in a
+    /// store configured by `options`.
+    pub fn add_layer(&mut self, options: JournalStoreOptions) -> WaterfallLayer<'_, State> {
+        let output = self.engine.new_journal_store(options);
+        let input = self.output_reader.clone_from_start();
+        WaterfallLayer {
+            waterfall: self,
+            input,
+            output,
+        }
+    }
+
+    /// Appends an item to the top store.
+    pub fn send(&mut self, state: &State) {
+        self.input.append(state);
+    }
+
+    /// Returns a reader of the bottom store, positioned at its first item.
+    pub fn reader(&self) -> StoreJournalReader<State> {
+        self.output_reader.clone_from_start()
+    }
+
+    /// Returns the number of layers running a transform.
+    pub fn layer_count(&self) -> usize {
+        self.layer_count
+    }
+}
+
+impl<State: Pod + Send + 'static> WaterfallLayer<'_, State> {
+    /// Returns the reader of the store this layer consumes.
+    pub fn input_store(&self) -> &StoreJournalReader<State> {
+        &self.input
+    }
+
+    /// Returns the store this layer writes to.
+    pub fn output_store(&self) -> &JournalStore<State> {
+        &self.output
+    }
+
+    /// Spawns a worker that appends `transform(item)` to the output store for every item of
+    /// the input store, skipping items for which it returns `None`.
+    ///
+    /// The output store becomes the new bottom of the waterfall.
+    pub fn run_transform(self, mut transform: impl FnMut(State) -> Option<State> + Send + 'static) {
+        let Self {
+            waterfall,
+            input,
+            mut output,
+        } = self;
+        waterfall.output_reader = output.reader();
+        waterfall.layer_count += 1;
+        waterfall.engine.run_worker(move || {
+            input.handle_remaining(|state| {
+                if let Some(out) = transform(*state) {
+                    output.append(&out);
+                }
+            }) > 0
+        });
+    }
+}
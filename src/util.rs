@@ -0,0 +1,177 @@
+use std::hint::spin_loop;
+use std::thread;
+use std::time::Duration;
+
+/// Backs off progressively while a worker has nothing to do.
+///
+/// Spins for the first 1000 calls, yields the thread up to 10000 calls, then sleeps for
+/// 100µs and resets `spin_count`. Reset `spin_count` to 0 whenever work is found.
+#[inline(always)]
+pub fn adaptive_yield(spin_count: &mut u32) {
+    *spin_count += 1;
+    if *spin_count <= 1000 {
+        spin_loop();
+    } else if *spin_count <= 10_000 {
+        thread::yield_now();
+    } else {
+        thread::sleep(Duration::from_micros(100));
+        *spin_count = 0;
+    }
+}
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32C (Castagnoli) checksum of `bytes`.
+pub(crate) fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc = CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Returns the resident set size of the process in bytes, read from `/proc/self/status`.
+#[cfg(target_os = "linux")]
+pub(crate) fn resident_set_size() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: usize = line["VmRSS:".len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Returns the resident set size of the process in bytes, read with `task_info`.
+#[cfg(target_os = "macos")]
+pub(crate) fn resident_set_size() -> Option<usize> {
+    /// `mach_task_basic_info` from `<mach/task_info.h>`, which is packed to 4 bytes.
+    #[repr(C, packed(4))]
+    #[derive(Default)]
+    struct MachTaskBasicInfo {
+        virtual_size: u64,
+        resident_size: u64,
+        resident_size_max: u64,
+        user_time: [i32; 2],
+        system_time: [i32; 2],
+        policy: i32,
+        suspend_count: i32,
+    }
+
+    const MACH_TASK_BASIC_INFO: u32 = 20;
+    const KERN_SUCCESS: i32 = 0;
+
+    unsafe extern "C" {
+        static mach_task_self_: u32;
+        fn task_info(task: u32, flavor: u32, info: *mut i32, count: *mut u32) -> i32;
+    }
+
+    let mut info = MachTaskBasicInfo::default();
+    let mut count = (size_of::<MachTaskBasicInfo>() / size_of::<i32>()) as u32;
+    let result = unsafe {
+        task_info(
+            mach_task_self_,
+            MACH_TASK_BASIC_INFO,
+            (&raw mut info).cast(),
+            &mut count,
+        )
+    };
+    (result == KERN_SUCCESS).then_some(info.resident_size as usize)
+}
+
+/// Returns the resident set size (working set) of the process in bytes, read with
+/// `GetProcessMemoryInfo`.
+#[cfg(windows)]
+pub(crate) fn resident_set_size() -> Option<usize> {
+    /// `PROCESS_MEMORY_COUNTERS` from `<psapi.h>`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct ProcessMemoryCounters {
+        cb: u32,
+        page_fault_count: u32,
+        peak_working_set_size: usize,
+        working_set_size: usize,
+        quota_peak_paged_pool_usage: usize,
+        quota_paged_pool_usage: usize,
+        quota_peak_non_paged_pool_usage: usize,
+        quota_non_paged_pool_usage: usize,
+        pagefile_usage: usize,
+        peak_pagefile_usage: usize,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        // Exported by kernel32 as `K32GetProcessMemoryInfo` since Windows 7
+        fn K32GetProcessMemoryInfo(
+            process: *mut std::ffi::c_void,
+            counters: *mut ProcessMemoryCounters,
+            cb: u32,
+        ) -> i32;
+    }
+
+    let mut counters = ProcessMemoryCounters {
+        cb: size_of::<ProcessMemoryCounters>() as u32,
+        ..Default::default()
+    };
+    let cb = counters.cb;
+    let ok = unsafe { K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, cb) };
+    (ok != 0).then_some(counters.working_set_size)
+}
+
+/// Reading the resident set size is not supported on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub(crate) fn resident_set_size() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_yield_resets_after_sleep() {
+        let mut spin_count = 0;
+        for _ in 0..10_000 {
+            adaptive_yield(&mut spin_count);
+        }
+        assert_eq!(spin_count, 10_000);
+
+        adaptive_yield(&mut spin_count);
+        assert_eq!(spin_count, 0);
+    }
+
+    #[test]
+    fn test_crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    #[test]
+    fn test_resident_set_size() {
+        let rss = resident_set_size().unwrap();
+        assert!(rss > 0);
+        assert_eq!(rss % 1024, 0);
+    }
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "arrow")]
+
+use arrow::array::{Float64Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use roda_state::{
+    ArrowField, ArrowSchema, JournalStoreOptions, RodaEngine, journal_store_from_arrow,
+    journal_store_to_arrow, try_journal_store_from_arrow,
+};
+use std::mem::offset_of;
+use std::sync::Arc;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Trade {
+    ts: u64,
+    price: f64,
+    size: u32,
+    side: i32,
+}
+
+impl ArrowSchema for Trade {
+    fn arrow_fields() -> Vec<ArrowField> {
+        vec![
+            ArrowField {
+                name: "ts",
+                data_type: DataType::UInt64,
+                offset: offset_of!(Trade, ts),
+            },
+            ArrowField {
+                name: "price",
+                data_type: DataType::Float64,
+                offset: offset_of!(Trade, price),
+            },
+            ArrowField {
+                name: "size",
+                data_type: DataType::UInt32,
+                offset: offset_of!(Trade, size),
+            },
+            ArrowField {
+                name: "side",
+                data_type: DataType::Int32,
+                offset: offset_of!(Trade, side),
+            },
+        ]
+    }
+}
+
+fn options(name: &'static str, size: usize) -> JournalStoreOptions {
+    JournalStoreOptions {
+        name: name.into(),
+        size,
+        in_memory: true,
+    }
+}
+
+#[test]
+fn test_arrow_round_trip() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<Trade>(options("arrow_source", 16));
+    let trades: Vec<Trade> = (0..10)
+        .map(|i| Trade {
+            ts: 1_000 + i,
+            price: 100.0 + i as f64 * 0.25,
+            size: i as u32 * 10,
+            side: if i % 2 == 0 { 1 } else { -1 },
+        })
+        .collect();
+    store.bulk_append(&trades);
+
+    let batch = journal_store_to_arrow(&store).unwrap();
+    assert_eq!(batch.num_rows(), 10);
+    assert_eq!(batch.num_columns(), 4);
+
+    let copy = journal_store_from_arrow::<Trade>(&batch, &engine, options("arrow_copy", 16));
+    let mut items = Vec::new();
+    copy.for_each_from(0, |t| items.push(*t));
+    assert_eq!(items, trades);
+}
+
+#[test]
+fn test_try_from_arrow_rejects_mismatched_column() {
+    let engine = RodaEngine::new();
+    let schema = Schema::new(vec![
+        Field::new("ts", DataType::UInt64, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("size", DataType::Float64, false),
+        Field::new("side", DataType::Float64, false),
+    ]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt64Array::from(vec![1u64])),
+            Arc::new(Float64Array::from(vec![1.0])),
+            Arc::new(Float64Array::from(vec![1.0])),
+            Arc::new(Float64Array::from(vec![1.0])),
+        ],
+    )
+    .unwrap();
+
+    let result = try_journal_store_from_arrow::<Trade>(&batch, &engine, options("arrow_bad", 4));
+    assert!(result.is_err());
+}
@@ -7,7 +7,7 @@ use std::thread;
 fn test_store_reader_edge_cases() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "edge_cases",
+        name: "edge_cases".into(),
         size: 1024,
         in_memory: true,
     });
@@ -60,7 +60,7 @@ fn test_store_full_capacity() {
     let engine = RodaEngine::new();
     let num_items = 10;
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "full_capacity",
+        name: "full_capacity".into(),
         size: num_items,
         in_memory: true,
     });
@@ -88,7 +88,7 @@ fn test_store_full_capacity() {
 fn test_store_overflow_panic() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "overflow",
+        name: "overflow".into(),
         size: 1,
         in_memory: true,
     });
@@ -101,7 +101,7 @@ fn test_store_overflow_panic() {
 fn test_store_concurrent_load() {
     let engine = Arc::new(RodaEngine::new());
     let store_options = JournalStoreOptions {
-        name: "concurrent_load",
+        name: "concurrent_load".into(),
         size: 1024 * 1024,
         in_memory: true,
     };
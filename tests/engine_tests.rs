@@ -0,0 +1,390 @@
+use roda_state::EngineMemorySnapshot;
+use roda_state::JournalStoreOptions;
+use roda_state::RodaEngine;
+use roda_state::StoreMetadata;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_total_ops_counts_reads() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
+        name: "total_ops".into(),
+        size: 16,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    assert_eq!(engine.total_ops(), 0);
+
+    store.append(&1);
+    store.append(&2);
+    assert!(reader.next());
+    assert!(reader.next());
+
+    assert_eq!(engine.total_ops(), 2);
+}
+
+#[test]
+fn test_await_idle_with_progress_ticks() {
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "progress".into(),
+        size: 1_000_000,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    let mut i = 0u64;
+    engine.run_worker(move || {
+        if i < 1_000_000 {
+            store.append(&i);
+            i += 1;
+        }
+        thread::sleep(Duration::from_micros(100));
+        true
+    });
+    engine.run_worker(move || reader.handle_remaining(|_| {}) > 0);
+
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let ticks_inner = ticks.clone();
+    engine.await_idle_with_progress(
+        Duration::from_millis(100),
+        Duration::from_millis(10),
+        move |_ops| {
+            ticks_inner.fetch_add(1, Ordering::Relaxed);
+        },
+    );
+
+    assert!(ticks.load(Ordering::Relaxed) >= 1);
+    assert!(engine.total_ops() > 0);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_async_producer_feeds_sync_consumer() {
+    let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "async_producer".into(),
+        size: 1024,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    engine.run_worker_async(runtime.clone(), async move {
+        for i in 0..100u64 {
+            store.append(&i);
+            tokio::task::yield_now().await;
+        }
+    });
+
+    let sum = Arc::new(AtomicUsize::new(0));
+    let sum_inner = sum.clone();
+    engine.run_worker(move || {
+        reader.handle_remaining(|x| {
+            sum_inner.fetch_add(*x as usize, Ordering::Relaxed);
+        }) > 0
+    });
+
+    engine.await_idle(Duration::from_secs(5));
+
+    assert_eq!(sum.load(Ordering::Relaxed), (0..100).sum::<usize>());
+    assert!(!engine.is_any_worker_panicked());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_async_store_writer() {
+    use roda_state::AsyncStoreWriter;
+    use tokio::sync::mpsc::error::TrySendError;
+
+    let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+    let mut engine = RodaEngine::new();
+    let store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "async_writer".into(),
+        size: 1024,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    let writer = AsyncStoreWriter::new(&mut engine, runtime.clone(), store, 4);
+    let producer = writer.clone();
+    runtime.block_on(async move {
+        for i in 0..100u64 {
+            producer.send(i).await;
+        }
+    });
+
+    let mut sent = 0;
+    while sent < 10 {
+        match writer.try_send(100 + sent) {
+            Ok(()) => sent += 1,
+            Err(TrySendError::Full(item)) => assert_eq!(item, 100 + sent),
+            Err(TrySendError::Closed(_)) => panic!("writer task stopped"),
+        }
+    }
+    drop(writer);
+
+    engine.await_idle(Duration::from_secs(5));
+
+    let mut items = Vec::new();
+    reader.handle_remaining(|x| items.push(*x));
+    assert_eq!(items, (0..110).collect::<Vec<_>>());
+    assert!(!engine.is_any_worker_panicked());
+}
+
+#[test]
+fn test_store_and_worker_count() {
+    let mut engine = RodaEngine::new();
+    assert_eq!(engine.store_count(), 0);
+    assert_eq!(engine.worker_count(), 0);
+    assert!(engine.is_running());
+
+    let stores: Vec<_> = ["count_a", "count_b", "count_c"]
+        .into_iter()
+        .map(|name| {
+            engine.new_journal_store::<u32>(JournalStoreOptions {
+                name: name.into(),
+                size: 4,
+                in_memory: true,
+            })
+        })
+        .collect();
+    assert_eq!(engine.store_count(), 3);
+
+    for store in stores.iter().take(2) {
+        let reader = store.reader();
+        engine.run_worker(move || reader.next());
+    }
+    assert_eq!(engine.worker_count(), 2);
+
+    assert!(engine.stop_and_drain(Duration::from_secs(5)));
+    assert!(!engine.is_running());
+    assert_eq!(engine.worker_count(), 0);
+    assert_eq!(engine.store_count(), 3);
+}
+
+#[test]
+fn test_store_registry() {
+    let mut engine = RodaEngine::new();
+    let mut prices = engine.new_journal_store::<f64>(JournalStoreOptions {
+        name: "prices".into(),
+        size: 10,
+        in_memory: true,
+    });
+    let mut ids = engine.new_journal_store::<u32>(JournalStoreOptions {
+        name: "ids".into(),
+        size: 4,
+        in_memory: true,
+    });
+    let flags = engine.new_journal_store::<u8>(JournalStoreOptions {
+        name: "flags".into(),
+        size: 8,
+        in_memory: true,
+    });
+    engine.register_store("prices", &prices);
+    engine.register_store("ids", &ids);
+    engine.register_store("flags", &flags);
+
+    for i in 0..5 {
+        prices.append(&(i as f64));
+    }
+    ids.append(&1);
+
+    assert_eq!(
+        engine.list_stores(),
+        vec![
+            StoreMetadata {
+                name: "flags",
+                count: 0,
+                capacity: 8,
+                element_size: 1,
+            },
+            StoreMetadata {
+                name: "ids",
+                count: 1,
+                capacity: 4,
+                element_size: 4,
+            },
+            StoreMetadata {
+                name: "prices",
+                count: 5,
+                capacity: 10,
+                element_size: 8,
+            },
+        ]
+    );
+    assert_eq!(engine.store_utilisation("prices"), Some(0.5));
+    assert_eq!(engine.store_utilisation("ids"), Some(0.25));
+    assert_eq!(engine.store_utilisation("unknown"), None);
+
+    drop(flags);
+    assert_eq!(engine.list_stores().len(), 2);
+    assert_eq!(engine.store_utilisation("flags"), None);
+}
+
+#[test]
+fn test_memory_snapshot() {
+    let mut engine = RodaEngine::new();
+    let mut prices = engine.new_journal_store::<f64>(JournalStoreOptions {
+        name: "prices".into(),
+        size: 10,
+        in_memory: true,
+    });
+    let ids = engine.new_journal_store::<u32>(JournalStoreOptions {
+        name: "ids".into(),
+        size: 4,
+        in_memory: true,
+    });
+    prices.append(&1.0);
+    assert_eq!(prices.memory_usage(), 80);
+    assert_eq!(ids.memory_usage(), 16);
+
+    engine.register_store("prices", &prices);
+    engine.register_store("ids", &ids);
+    assert_eq!(
+        engine.memory_snapshot(),
+        EngineMemorySnapshot {
+            stores: vec![("ids".to_string(), 16), ("prices".to_string(), 80)],
+            total_bytes: 96,
+        }
+    );
+
+    drop(ids);
+    assert_eq!(engine.memory_snapshot().total_bytes, 80);
+}
+
+#[test]
+fn test_stop_and_drain_processes_remaining_items() {
+    let mut engine = RodaEngine::new();
+    let mut input = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "drain_input".into(),
+        size: 128,
+        in_memory: true,
+    });
+    let mut middle = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "drain_middle".into(),
+        size: 128,
+        in_memory: true,
+    });
+    let mut output = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "drain_output".into(),
+        size: 128,
+        in_memory: true,
+    });
+    let input_reader = input.reader();
+    let middle_reader = middle.reader();
+    let output_reader = output.reader();
+
+    // Slow stages, so that most items are still pending when draining starts
+    engine.run_worker(move || {
+        input_reader.handle_remaining_up_to(1, |x| {
+            thread::sleep(Duration::from_micros(200));
+            middle.append(x);
+        }) > 0
+    });
+    engine.run_worker(move || {
+        middle_reader.handle_remaining_up_to(1, |x| {
+            thread::sleep(Duration::from_micros(200));
+            output.append(&(x * 2));
+        }) > 0
+    });
+
+    for i in 0..100 {
+        input.append(&i);
+    }
+
+    assert!(engine.stop_and_drain(Duration::from_secs(5)));
+
+    assert_eq!(output_reader.size(), 100);
+    for i in 0..100 {
+        assert_eq!(output_reader.get_at(i), Some(i as u64 * 2));
+    }
+}
+
+#[test]
+fn test_bench_worker() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "bench_worker".into(),
+        size: 1_500,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    let mut i = 0u64;
+    let result = engine.bench_worker(500, 1_000, || {
+        store.append(&i);
+        i += 1;
+        true
+    });
+
+    assert_eq!(result.iterations, 1_000);
+    assert_eq!(result.latency.count, 1_000);
+    assert!(result.latency.min <= result.latency.p50);
+    assert!(result.throughput > 0.0);
+    assert_eq!(engine.worker_count(), 0);
+    assert_eq!(reader.size(), 1_500);
+    assert_eq!(i, 1_500);
+
+    let result = engine.bench_worker(0, 0, || true);
+    assert_eq!(result.iterations, 0);
+    assert!(result.throughput.is_finite());
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+#[test]
+fn test_on_memory_pressure() {
+    let mut engine = RodaEngine::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let rss = Arc::new(AtomicUsize::new(0));
+    let (calls_inner, rss_inner) = (calls.clone(), rss.clone());
+    engine
+        .on_memory_pressure(1, move |current| {
+            calls_inner.fetch_add(1, Ordering::Relaxed);
+            rss_inner.store(current, Ordering::Relaxed);
+        })
+        .unwrap();
+    engine
+        .on_memory_pressure(usize::MAX, |_| panic!("threshold not exceeded"))
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(350));
+    assert!(engine.stop_and_drain(Duration::from_secs(1)));
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert!(rss.load(Ordering::Relaxed) > 1);
+}
+
+#[cfg(all(feature = "profiling", unix))]
+#[test]
+fn test_profile_worker() {
+    use roda_state::ProfileError;
+
+    let mut engine = RodaEngine::new();
+    engine.run_worker(|| {
+        let mut x = 0u64;
+        for i in 0..10_000 {
+            x = std::hint::black_box(x.wrapping_mul(31).wrapping_add(i));
+        }
+        x > 0
+    });
+    engine.run_worker(|| false);
+
+    let profile = engine
+        .profile_worker(0, Duration::from_millis(300))
+        .unwrap();
+    assert!(!profile.samples.is_empty());
+    assert!(!profile.top_functions.is_empty());
+    let total: u32 = profile.samples.iter().map(|s| s.count).sum();
+    assert!(profile.top_functions[0].1 <= total);
+    assert!(profile.to_flamegraph_svg().contains("<svg"));
+
+    assert!(matches!(
+        engine.profile_worker(5, Duration::from_millis(10)),
+        Err(ProfileError::UnknownWorker(5))
+    ));
+}
@@ -0,0 +1,98 @@
+use roda_state::{HealthCheck, JournalStoreOptions, RodaEngine};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "condition not met"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_health_check_worker_panic() {
+    let mut engine = RodaEngine::new();
+    let mut health = HealthCheck::new(&engine);
+    let panics = Arc::new(AtomicUsize::new(0));
+    {
+        let panics = panics.clone();
+        health.on_worker_panic(move || {
+            panics.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    health.start(Duration::from_millis(5));
+    assert!(health.is_healthy());
+
+    engine.run_worker(|| panic!("worker failed"));
+
+    wait_for(|| panics.load(Ordering::Relaxed) > 0);
+    assert!(!health.is_healthy());
+
+    // The callback fires once per failure, not on every check
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(panics.load(Ordering::Relaxed), 1);
+
+    // Dropping the engine would re-raise the worker panic while joining it
+    drop(health);
+    std::mem::forget(engine);
+}
+
+#[test]
+fn test_health_check_store_full() {
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "health_store".into(),
+        size: 4,
+        in_memory: true,
+    });
+    engine.register_store("health_store", &store);
+
+    let mut health = HealthCheck::new(&engine);
+    let alerts = Arc::new(std::sync::Mutex::new(Vec::new()));
+    {
+        let alerts = alerts.clone();
+        health.on_store_full(0.75, move |name| {
+            alerts.lock().unwrap().push(name.to_string())
+        });
+    }
+    health.start(Duration::from_millis(5));
+
+    store.append(&1);
+    store.append(&2);
+    thread::sleep(Duration::from_millis(30));
+    assert!(alerts.lock().unwrap().is_empty());
+
+    store.append(&3);
+    wait_for(|| !alerts.lock().unwrap().is_empty());
+    assert!(health.is_healthy());
+
+    store.append(&4);
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(*alerts.lock().unwrap(), vec!["health_store".to_string()]);
+    assert!(!health.is_healthy());
+}
+
+#[cfg(feature = "http-health")]
+#[test]
+fn test_health_check_http_endpoint() {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let engine = RodaEngine::new();
+    let mut health = HealthCheck::new(&engine);
+    health.http_endpoint(("127.0.0.1", 18_765)).unwrap();
+
+    let mut stream = TcpStream::connect(("127.0.0.1", 18_765)).unwrap();
+    stream.write_all(b"GET /health HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.ends_with("{\"healthy\":true,\"worker_panicked\":false,\"stores\":[]}"));
+}
@@ -1,12 +1,17 @@
+use roda_state::ExponentialBackoffReader;
+use roda_state::JournalStore;
 use roda_state::JournalStoreOptions;
 use roda_state::RodaEngine;
+use roda_state::RodaError;
+use roda_state::testing::MockClock;
+use std::time::Duration;
 
 #[test]
 #[should_panic(expected = "Store is full")]
 fn test_journal_panic_when_full() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "full_test",
+        name: "full_test".into(),
         size: 2, // Can hold only 2 u64
         in_memory: true,
     });
@@ -16,11 +21,39 @@ fn test_journal_panic_when_full() {
     store.append(&3); // This should panic
 }
 
+#[test]
+fn test_journal_try_append_when_full() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "try_append_test".into(),
+        size: 2,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    assert_eq!(store.try_append(&1), Ok(()));
+    assert_eq!(store.try_append(&2), Ok(()));
+    let err = store.try_append(&3).unwrap_err();
+    assert_eq!(
+        err,
+        RodaError::StoreFull {
+            capacity: 16,
+            position: 16,
+            item_size: 8,
+        }
+    );
+    assert!(err.to_string().starts_with("Store is full"));
+
+    assert_eq!(store.size(), 2);
+    assert_eq!(reader.get_at(1), Some(2));
+    assert_eq!(reader.get_at(2), None);
+}
+
 #[test]
 fn test_journal_no_circularity() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
-        name: "no_circular_test",
+        name: "no_circular_test".into(),
         size: 2,
         in_memory: true,
     });
@@ -35,3 +68,424 @@ fn test_journal_no_circularity() {
     // In the old circular store, if we pushed more, it would overwrite.
     // Here it just panics, so we just verify we can read what we pushed.
 }
+
+#[test]
+fn test_journal_extend_capacity() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "extend_test".into(),
+        size: 2,
+        in_memory: true,
+    });
+
+    store.append(&1);
+    store.append(&2);
+    store.extend_capacity(4).unwrap();
+    store.append(&3);
+    store.append(&4);
+
+    let reader = store.reader();
+    assert_eq!(store.size(), 4);
+    assert_eq!(reader.get_at(0), Some(1));
+    assert_eq!(reader.get_at(1), Some(2));
+    assert_eq!(reader.get_at(2), Some(3));
+    assert_eq!(reader.get_at(3), Some(4));
+}
+
+#[test]
+fn test_journal_extend_capacity_with_readers() {
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "extend_readers_test".into(),
+        size: 2,
+        in_memory: true,
+    });
+    engine.register_store("extend_readers_test", &store);
+    store.append(&1);
+
+    let reader = store.reader();
+    let err = store.extend_capacity(4).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    assert_eq!(reader.handle_remaining(|_| {}), 1);
+
+    drop(reader);
+    store.extend_capacity(4).unwrap();
+    store.append(&2);
+    store.append(&3);
+
+    // The registry keeps following the store
+    assert_eq!(engine.list_stores()[0].count, 3);
+}
+
+#[test]
+#[should_panic(expected = "Store is full")]
+fn test_journal_extend_capacity_is_bounded() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "extend_bounded_test".into(),
+        size: 1,
+        in_memory: true,
+    });
+
+    store.append(&1);
+    store.extend_capacity(2).unwrap();
+    store.append(&2);
+    store.append(&3); // This should panic
+}
+
+#[test]
+fn test_journal_bulk_append() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "bulk_append_test".into(),
+        size: 8,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    store.append(&1);
+    store.bulk_append(&[2, 3, 4]);
+    store.bulk_append(&[]);
+    assert_eq!(store.size(), 4);
+    assert!(!store.append_if_changed(&4, |last, new| last != new));
+
+    let mut seen = Vec::new();
+    reader.handle_remaining(|v| seen.push(*v));
+    assert_eq!(seen, vec![1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "Store is full")]
+fn test_journal_bulk_append_panics_when_full() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "bulk_append_full_test".into(),
+        size: 2,
+        in_memory: true,
+    });
+    store.bulk_append(&[1, 2, 3]);
+}
+
+#[test]
+fn test_journal_append_if_changed() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "append_if_changed_test".into(),
+        size: 16,
+        in_memory: true,
+    });
+    let reader = store.reader();
+
+    let appended: Vec<bool> = [1, 1, 2, 2, 2, 1, 3]
+        .iter()
+        .map(|v| store.append_if_changed(v, |last, new| last != new))
+        .collect();
+
+    assert_eq!(appended, vec![true, false, true, false, false, true, true]);
+    assert_eq!(store.size(), 4);
+    let mut seen = Vec::new();
+    reader.handle_remaining(|v| seen.push(*v));
+    assert_eq!(seen, vec![1, 2, 1, 3]);
+}
+
+#[test]
+fn test_journal_next_or_yield() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "next_or_yield_test".into(),
+        size: 4,
+        in_memory: true,
+    });
+    let reader = store.reader();
+    let mut spin = 0;
+
+    assert!(!reader.next_or_yield(&mut spin));
+    assert!(!reader.next_or_yield(&mut spin));
+    assert_eq!(spin, 2);
+
+    store.append(&7);
+    assert!(reader.next_or_yield(&mut spin));
+    assert_eq!(spin, 0);
+    assert_eq!(reader.get(), Some(7));
+}
+
+#[test]
+fn test_journal_reader_peek() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "peek_test".into(),
+        size: 4,
+        in_memory: true,
+    });
+    let reader = store.reader();
+    assert_eq!(reader.peek(), None);
+
+    store.append(&10);
+    store.append(&20);
+    store.append(&30);
+
+    assert_eq!(reader.peek(), Some(10));
+    assert_eq!(reader.peek_at(2), Some(30));
+    assert_eq!(reader.peek_at(3), None);
+    assert_eq!(reader.get_index(), 0);
+
+    assert!(reader.next());
+    assert_eq!(reader.get(), Some(10));
+    assert_eq!(reader.peek(), Some(20));
+    assert_eq!(reader.peek_at(1), Some(30));
+    assert!(reader.next());
+    assert_eq!(reader.get(), Some(20));
+}
+
+#[test]
+fn test_journal_for_each_from() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "for_each_from_test".into(),
+        size: 16,
+        in_memory: true,
+    });
+    for i in 0..10 {
+        store.append(&(i * 3));
+    }
+    let reader = store.reader();
+
+    for start in 0..12 {
+        let mut items = Vec::new();
+        store.for_each_from(start, |x| items.push(*x));
+        let expected: Vec<u64> = (start..10).filter_map(|i| reader.get_at(i)).collect();
+        assert_eq!(items, expected);
+
+        for end in start..14 {
+            let mut items = Vec::new();
+            store.for_each_from_to(start, end, |x| items.push(*x));
+            let expected: Vec<u64> = (start..end).filter_map(|i| reader.get_at(i)).collect();
+            assert_eq!(items, expected);
+        }
+    }
+
+    // Out of range bounds are ignored
+    let mut called = false;
+    store.for_each_from_to(5, 2, |_| called = true);
+    store.for_each_from(10, |_| called = true);
+    assert!(!called);
+}
+
+#[test]
+fn test_journal_reader_into_channel() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "into_channel_test".into(),
+        size: 128,
+        in_memory: true,
+    });
+    for i in 0..100 {
+        store.append(&i);
+    }
+
+    // A small buffer makes the sender wait for the receiver
+    let (receiver, handle) = store.reader().into_channel(4);
+    assert_eq!(receiver.iter().take(100).count(), 100);
+
+    // A quiet store keeps the channel open
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    store.append(&100);
+    assert_eq!(receiver.recv(), Ok(100));
+
+    // Dropping the store ends the channel after the remaining items
+    store.append(&101);
+    drop(store);
+    let received: Vec<u64> = receiver.iter().collect();
+    handle.join().unwrap();
+    assert_eq!(received, vec![101]);
+}
+
+#[test]
+fn test_journal_exponential_backoff_reader() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "backoff_reader_test".into(),
+        size: 16,
+        in_memory: true,
+    });
+    let clock = MockClock::new(0);
+    let sleep_clock = clock.clone();
+    let mut reader = ExponentialBackoffReader::new(store.reader())
+        .with_sleep_fn(move |d| sleep_clock.advance(d.as_nanos() as u64));
+    reader.set_max_backoff(Duration::from_micros(10));
+
+    let mut backoffs = Vec::new();
+    for _ in 0..6 {
+        assert!(!reader.next_with_backoff());
+        backoffs.push(reader.current_backoff().as_micros());
+    }
+    assert_eq!(backoffs, vec![1, 2, 4, 8, 10, 10]);
+    assert_eq!(clock.now(), 35_000);
+
+    store.append(&7);
+    assert!(reader.next_with_backoff());
+    assert_eq!(reader.current_backoff(), Duration::ZERO);
+    assert_eq!(reader.reader().get(), Some(7));
+    assert_eq!(clock.now(), 35_000);
+
+    assert!(!reader.next_with_backoff());
+    assert_eq!(reader.current_backoff(), Duration::from_micros(1));
+}
+
+#[test]
+fn test_journal_binary_search() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "binary_search_test".into(),
+        size: 10_000,
+        in_memory: true,
+    });
+    assert_eq!(store.binary_search_by(|x| x.cmp(&0)), None);
+    assert_eq!(store.find_first_at_or_after(0, |x| *x), None);
+
+    let items: Vec<u64> = (0..10_000).map(|i| i * 3).collect();
+    store.bulk_append(&items);
+
+    for target in (0..30_005).step_by(7) {
+        assert_eq!(
+            store.binary_search_by(|x| x.cmp(&target)),
+            items.iter().position(|x| *x == target)
+        );
+        assert_eq!(
+            store.find_first_at_or_after(target, |x| *x),
+            items.iter().position(|x| *x >= target)
+        );
+    }
+}
+
+#[test]
+fn test_journal_compact() {
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "compact_test".into(),
+        size: 128,
+        in_memory: true,
+    });
+    for i in 0..100 {
+        store.append(&i);
+    }
+
+    let compacted = store.compact(|x| x % 2 == 0);
+    assert_eq!(store.size(), 100);
+    assert_eq!(compacted.size(), 50);
+    let mut items = Vec::new();
+    compacted.for_each_from(0, |x| items.push(*x));
+    assert_eq!(items, (0..100).step_by(2).collect::<Vec<u64>>());
+
+    engine.register_store("compact_test", &store);
+    let reader = store.reader();
+    let err = store.compact_in_place(|x| x % 2 == 0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    assert_eq!(store.size(), 100);
+
+    drop(reader);
+    store.compact_in_place(|x| x % 2 == 0).unwrap();
+    assert_eq!(store.size(), 50);
+    assert!(!store.append_if_changed(&98, |a, b| a != b));
+    assert_eq!(engine.list_stores()[0].count, 50);
+}
+
+#[test]
+fn test_journal_compact_in_place_file_backed() {
+    let root = std::env::temp_dir().join(format!("roda_compact_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let root: &'static str = Box::leak(root.to_str().unwrap().to_string().into_boxed_str());
+    let engine = RodaEngine::new_with_root_path(root);
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "compact_file".into(),
+        size: 128,
+        in_memory: false,
+    });
+    for i in 0..100 {
+        store.append(&i);
+    }
+
+    store.compact_in_place(|x| x % 2 == 0).unwrap();
+    assert_eq!(store.size(), 50);
+    store.append(&100);
+
+    let mut items = Vec::new();
+    store.for_each_from(0, |x| items.push(*x));
+    assert_eq!(items, (0..=100).step_by(2).collect::<Vec<u64>>());
+
+    let path = format!("{}/compact_file.store", root);
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.len(), 128 * 8);
+    assert_eq!(&bytes[8..16], &2u64.to_ne_bytes());
+    assert!(!std::path::Path::new(&format!("{}/compact_file.store.compact", root)).exists());
+
+    drop(store);
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn test_journal_store_from_fn_and_slice() {
+    let store = JournalStore::from_fn(
+        "data",
+        10,
+        |i| i as u32,
+        JournalStoreOptions {
+            name: "from_fn_test".into(),
+            size: 10,
+            in_memory: true,
+        },
+    );
+    let reader = store.reader();
+    for i in 0..10 {
+        assert_eq!(reader.get_at(i), Some(i as u32));
+    }
+    assert_eq!(reader.get_at(10), None);
+
+    let store = JournalStore::from_slice(
+        "data",
+        &[3u64, 1, 2],
+        JournalStoreOptions {
+            name: "from_slice_test".into(),
+            size: 8,
+            in_memory: true,
+        },
+    );
+    assert_eq!(store.size(), 3);
+    assert_eq!(store.reader().get_at(2), Some(2));
+}
+
+#[test]
+fn test_journal_store_from_slice_file_backed() {
+    let root = std::env::temp_dir().join(format!("roda_from_slice_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let root: &'static str = Box::leak(root.to_str().unwrap().to_string().into_boxed_str());
+    let store = JournalStore::from_slice(
+        root,
+        &[7u64, 8],
+        JournalStoreOptions {
+            name: "from_slice_file".into(),
+            size: 4,
+            in_memory: false,
+        },
+    );
+    assert_eq!(store.reader().get_at(1), Some(8));
+    assert!(std::path::Path::new(&format!("{}/from_slice_file.store", root)).exists());
+
+    drop(store);
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+#[should_panic(expected = "Store is too small. Capacity: 4, Items: 5")]
+fn test_journal_store_from_fn_too_small() {
+    JournalStore::from_fn(
+        "data",
+        5,
+        |i| i as u64,
+        JournalStoreOptions {
+            name: "from_fn_too_small".into(),
+            size: 4,
+            in_memory: true,
+        },
+    );
+}
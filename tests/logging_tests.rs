@@ -0,0 +1,133 @@
+use roda_state::testing::MockClock;
+use roda_state::{JournalStoreOptions, LogLevel, RodaEngine, Stage, progress, progress_with_clock};
+use spdlog::Record;
+use spdlog::sink::{GetSinkProp, Sink, SinkProp};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type Fields = Vec<(String, String)>;
+
+/// Keeps the message and key-value pairs of every logged record.
+#[derive(Default)]
+struct CaptureSink {
+    prop: SinkProp,
+    records: Mutex<Vec<(String, Fields)>>,
+}
+
+impl CaptureSink {
+    fn fields_of(&self, message: &str) -> Vec<Fields> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(payload, _)| payload.contains(message))
+            .map(|(_, kvs)| kvs.clone())
+            .collect()
+    }
+}
+
+impl GetSinkProp for CaptureSink {
+    fn prop(&self) -> &SinkProp {
+        &self.prop
+    }
+}
+
+impl Sink for CaptureSink {
+    fn log(&self, record: &Record) -> spdlog::Result<()> {
+        let kvs = record
+            .key_values()
+            .into_iter()
+            .map(|(k, v)| (k.as_str().to_string(), v.to_string()))
+            .collect();
+        self.records
+            .lock()
+            .unwrap()
+            .push((record.payload().to_string(), kvs));
+        Ok(())
+    }
+
+    fn flush(&self) -> spdlog::Result<()> {
+        Ok(())
+    }
+}
+
+fn field(key: &str, value: &str) -> (String, String) {
+    (key.to_string(), value.to_string())
+}
+
+// A single test, as the log level and sink are process-wide
+#[test]
+fn test_structured_log_events() {
+    let sink = Arc::new(CaptureSink::default());
+    RodaEngine::set_log_sink(sink.clone()).unwrap();
+
+    // Debug events are filtered out at the default level
+    let engine = RodaEngine::new();
+    let _ = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "quiet_store".into(),
+        size: 4,
+        in_memory: true,
+    });
+    assert!(sink.fields_of("Journal store created").is_empty());
+
+    RodaEngine::set_log_level(LogLevel::Debug);
+    // The process-wide default logger keeps its own level
+    let default_level = spdlog::default_logger().level_filter();
+    assert!(!default_level.test(spdlog::Level::Debug));
+    let mut engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "logged_store".into(),
+        size: 4,
+        in_memory: true,
+    });
+    assert_eq!(
+        sink.fields_of("Journal store created"),
+        vec![vec![
+            field("store_name", "logged_store"),
+            field("capacity", "4"),
+            field("in_memory", "true"),
+        ]]
+    );
+
+    let reader = store.reader();
+    engine.run_worker(move || reader.handle_remaining(|_| {}) > 0);
+    store.append(&1);
+    engine.await_idle(Duration::from_secs(1));
+    assert_eq!(
+        sink.fields_of("Worker started"),
+        vec![vec![field("worker_id", "0")]]
+    );
+
+    let mut pipe = progress::<u64>("ingest", 2);
+    for i in 0..2u64 {
+        pipe.process(&i, &mut |_: &u64| {});
+    }
+    let fields = sink.fields_of("[ingest] Processed");
+    assert_eq!(fields.len(), 1);
+    assert_eq!(
+        fields[0][..2],
+        [field("stage", "ingest"), field("count", "2")]
+    );
+
+    let clock = MockClock::new(0);
+    let mut pipe = progress_with_clock::<u64>("mocked", 2, clock.as_fn());
+    for i in 0..2u64 {
+        clock.advance(Duration::from_millis(500).as_nanos() as u64);
+        pipe.process(&i, &mut |_: &u64| {});
+    }
+    let fields = sink.fields_of("[mocked] Processed");
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].len(), 4);
+    // Two items per second, both for the last interval and on average
+    for (key, value) in &fields[0][2..] {
+        assert_eq!(value.parse::<f64>().unwrap(), 2.0, "{}", key);
+    }
+
+    RodaEngine::set_log_level(LogLevel::Off);
+    let _ = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "silent_store".into(),
+        size: 4,
+        in_memory: true,
+    });
+    assert_eq!(sink.fields_of("Journal store created").len(), 1);
+}
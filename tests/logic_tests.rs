@@ -5,7 +5,7 @@ use roda_state::RodaEngine;
 fn test_reader_next_and_with_logic() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "logic_test",
+        name: "logic_test".into(),
         size: 1024,
         in_memory: true,
     });
@@ -41,7 +41,7 @@ fn test_reader_next_and_with_logic() {
 fn test_reader_get_at_and_last() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "logic_test_2",
+        name: "logic_test_2".into(),
         size: 1024,
         in_memory: true,
     });
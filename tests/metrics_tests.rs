@@ -0,0 +1,107 @@
+#![cfg(feature = "prometheus")]
+
+use roda_state::{JournalStoreOptions, RodaEngine, collect_all_metrics};
+
+/// A lenient parser for the Prometheus text format. Returns the samples as
+/// `(metric, labels, value)` and checks that every sample has a declared type.
+fn parse_prometheus(text: &str) -> Vec<(String, String, f64)> {
+    let mut typed = Vec::new();
+    let mut samples = Vec::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let mut parts = rest.split_whitespace();
+            let name = parts.next().expect("TYPE without name");
+            let kind = parts.next().expect("TYPE without kind");
+            assert!(
+                ["counter", "gauge", "histogram", "summary", "untyped"].contains(&kind),
+                "unknown type {}",
+                kind
+            );
+            typed.push(name.to_string());
+            continue;
+        }
+        if line.starts_with('#') {
+            assert!(line.starts_with("# HELP "), "unexpected comment {}", line);
+            continue;
+        }
+        let (series, value) = line.rsplit_once(' ').expect("sample without value");
+        let (name, labels) = match series.split_once('{') {
+            Some((name, labels)) => (name, labels.strip_suffix('}').expect("unclosed labels")),
+            None => (series, ""),
+        };
+        assert!(
+            name.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':'),
+            "invalid metric name {}",
+            name
+        );
+        assert!(typed.iter().any(|t| t == name), "{} has no TYPE", name);
+        samples.push((
+            name.to_string(),
+            labels.to_string(),
+            value.parse().expect("invalid value"),
+        ));
+    }
+    samples
+}
+
+#[test]
+fn test_store_prometheus_counter() {
+    let engine = RodaEngine::new();
+    let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "metrics_counter".into(),
+        size: 8,
+        in_memory: true,
+    });
+    store.append(&1);
+    store.append(&2);
+    store.append(&3);
+
+    let text = store.to_prometheus_counter("trades_written_total");
+    assert_eq!(
+        text,
+        "# HELP trades_written_total Number of items appended to the store.\n\
+         # TYPE trades_written_total counter\n\
+         trades_written_total 3\n"
+    );
+    assert_eq!(
+        parse_prometheus(&text),
+        vec![("trades_written_total".to_string(), String::new(), 3.0)]
+    );
+}
+
+#[test]
+fn test_collect_all_metrics() {
+    let mut engine = RodaEngine::new();
+    let mut trades = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "metrics_trades".into(),
+        size: 4,
+        in_memory: true,
+    });
+    let quotes = engine.new_journal_store::<u32>(JournalStoreOptions {
+        name: "metrics_quotes".into(),
+        size: 10,
+        in_memory: true,
+    });
+    engine.register_store("quotes", &quotes);
+    engine.register_store("trades", &trades);
+    trades.append(&1);
+
+    let text = collect_all_metrics(&engine);
+    assert_eq!(
+        text,
+        "# HELP roda_store_items_total Number of items appended to the store.\n\
+         # TYPE roda_store_items_total counter\n\
+         roda_store_items_total{store=\"quotes\"} 0\n\
+         roda_store_items_total{store=\"trades\"} 1\n\
+         # HELP roda_store_capacity Maximum number of items the store can hold.\n\
+         # TYPE roda_store_capacity gauge\n\
+         roda_store_capacity{store=\"quotes\"} 10\n\
+         roda_store_capacity{store=\"trades\"} 4\n\
+         # HELP roda_store_utilisation Fraction of the store capacity in use.\n\
+         # TYPE roda_store_utilisation gauge\n\
+         roda_store_utilisation{store=\"quotes\"} 0\n\
+         roda_store_utilisation{store=\"trades\"} 0.25\n"
+    );
+    assert_eq!(parse_prometheus(&text).len(), 6);
+}
@@ -5,7 +5,7 @@ use roda_state::RodaEngine;
 fn test_push_then_read_single() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "test1",
+        name: "test1".into(),
         size: 1024,
         in_memory: true,
     });
@@ -21,7 +21,7 @@ fn test_push_then_read_single() {
 fn test_multiple_push_read_in_order() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "test2",
+        name: "test2".into(),
         size: 1024,
         in_memory: true,
     });
@@ -41,7 +41,7 @@ fn test_multiple_push_read_in_order() {
 fn test_interleaved_push_and_read() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "test3",
+        name: "test3".into(),
         size: 1024,
         in_memory: true,
     });
@@ -65,12 +65,12 @@ fn test_stores_are_isolated_by_type() {
     let engine = RodaEngine::new();
 
     let mut u_store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "u32",
+        name: "u32".into(),
         size: 1024,
         in_memory: true,
     });
     let mut i_store = engine.new_journal_store::<i64>(JournalStoreOptions {
-        name: "i64",
+        name: "i64".into(),
         size: 1024,
         in_memory: true,
     });
@@ -95,7 +95,7 @@ fn test_stores_are_isolated_by_type() {
 fn test_push_after_partial_reads() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "test4",
+        name: "test4".into(),
         size: 1024,
         in_memory: true,
     });
@@ -0,0 +1,89 @@
+use roda_state::{JournalStoreOptions, RodaEngine, SegmentIndex, SegmentMeta};
+use std::path::PathBuf;
+
+fn segment(segment_id: usize, first_ts: u64, last_ts: u64) -> SegmentMeta {
+    SegmentMeta {
+        segment_id,
+        first_ts,
+        last_ts,
+        path: PathBuf::from(format!("data/segment_{}.store", segment_id)),
+    }
+}
+
+fn ids(segments: &[SegmentMeta]) -> Vec<usize> {
+    segments.iter().map(|s| s.segment_id).collect()
+}
+
+#[test]
+fn test_segments_for_range() {
+    let mut index = SegmentIndex::new();
+    index.insert(segment(2, 200, 299));
+    index.insert(segment(0, 0, 99));
+    index.insert(segment(1, 100, 199));
+
+    assert_eq!(ids(index.segments()), vec![0, 1, 2]);
+    assert_eq!(ids(index.segments_for_range(0, 1000)), vec![0, 1, 2]);
+    assert_eq!(ids(index.segments_for_range(50, 150)), vec![0, 1]);
+    assert_eq!(ids(index.segments_for_range(99, 100)), vec![0]);
+    assert_eq!(ids(index.segments_for_range(100, 101)), vec![1]);
+    assert_eq!(ids(index.segments_for_range(150, 250)), vec![1, 2]);
+    assert_eq!(ids(index.segments_for_range(300, 400)), Vec::<usize>::new());
+    assert_eq!(ids(index.segments_for_range(120, 120)), Vec::<usize>::new());
+}
+
+#[test]
+fn test_segment_index_save_and_load() {
+    let mut index = SegmentIndex::new();
+    for i in 0..3 {
+        index.insert(segment(i, i as u64 * 100, i as u64 * 100 + 99));
+    }
+    let path = std::env::temp_dir().join(format!("segment_index_{}.tsv", std::process::id()));
+    index.save(&path).unwrap();
+    assert_eq!(SegmentIndex::load(&path).unwrap(), index);
+
+    std::fs::write(&path, "0\tnot a number\t1\tdata/x.store\n").unwrap();
+    assert!(SegmentIndex::load(&path).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_segment_index_update_from_store() {
+    let root = std::env::temp_dir().join(format!("roda_segments_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let root: &'static str = Box::leak(root.to_str().unwrap().to_string().into_boxed_str());
+    let engine = RodaEngine::new_with_root_path(root);
+
+    let mut index = SegmentIndex::new();
+    let mut stores = Vec::new();
+    for segment in 0..3u64 {
+        let mut store = engine.new_journal_store::<u64>(JournalStoreOptions {
+            name: format!("segment_{}", segment).into(),
+            size: 16,
+            in_memory: false,
+        });
+        store.append(&(segment * 100));
+        index.update_from_store(&store, |ts| *ts);
+        stores.push(store);
+    }
+    stores[2].append(&250);
+    index.update_from_store(&stores[2], |ts| *ts);
+
+    let in_memory = engine.new_journal_store::<u64>(JournalStoreOptions {
+        name: "segment_in_memory".into(),
+        size: 16,
+        in_memory: true,
+    });
+    index.update_from_store(&in_memory, |ts| *ts);
+
+    assert_eq!(ids(index.segments()), vec![0, 1, 2]);
+    let last = &index.segments()[2];
+    assert_eq!((last.first_ts, last.last_ts), (200, 250));
+    assert_eq!(
+        last.path,
+        PathBuf::from(format!("{}/segment_2.store", root))
+    );
+    assert_eq!(ids(index.segments_for_range(240, 260)), vec![2]);
+
+    drop(stores);
+    let _ = std::fs::remove_dir_all(root);
+}
@@ -1,4 +1,8 @@
-use roda_state::{OutputCollector, Stage, StageEngine, pipe};
+use roda_state::{
+    OutputCollector, Sequenced, Stage, StageEngine, assert_pipeline_types, filter, filter_map,
+    pipe, resequence, sequence_number, strip_sequence,
+};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
@@ -243,3 +247,374 @@ fn test_long_pipeline_heavy_load() {
         assert_eq!(engine.receive(), Some(i as u32 + stages as u32));
     }
 }
+
+#[test]
+fn test_try_receive_batch() {
+    let mut engine = StageEngine::<u32, u32>::new().add_stage(|x: &u32| Some(*x * 2));
+
+    for i in 0..8 {
+        engine.send(&i);
+    }
+    thread::sleep(Duration::from_millis(5));
+    engine.await_idle(Duration::from_millis(200));
+
+    assert_eq!(engine.try_receive_batch(5), vec![0, 2, 4, 6, 8]);
+    assert_eq!(engine.try_receive_batch(5), vec![10, 12, 14]);
+    assert!(engine.try_receive_batch(5).is_empty());
+}
+
+#[test]
+fn test_receive_batch() {
+    let mut engine = StageEngine::<u32, u32>::new().add_stage(|x: &u32| {
+        thread::sleep(Duration::from_millis(1));
+        Some(*x + 1)
+    });
+
+    for i in 0..5 {
+        engine.send(&i);
+    }
+
+    assert_eq!(
+        engine.receive_batch(3, Duration::from_secs(5)),
+        vec![1, 2, 3]
+    );
+    // Only 2 items remain, so this returns them after the timeout
+    assert_eq!(
+        engine.receive_batch(10, Duration::from_millis(50)),
+        vec![4, 5]
+    );
+    // A huge batch size does not allocate room for that many items up front
+    engine.send(&5);
+    assert_eq!(
+        engine.receive_batch(usize::MAX, Duration::from_millis(50)),
+        vec![6]
+    );
+}
+
+#[test]
+fn test_filter_stage() {
+    let mut engine = StageEngine::<u32, u32>::new().add_stage(filter(|x: &u32| *x >= 10));
+
+    for x in [5, 10, 3, 20, 15] {
+        engine.send(&x);
+    }
+
+    assert_eq!(engine.receive(), Some(10));
+    assert_eq!(engine.receive(), Some(20));
+    assert_eq!(engine.receive(), Some(15));
+    engine.await_idle(Duration::from_millis(50));
+    assert_eq!(engine.try_receive(), None);
+}
+
+#[test]
+fn test_filter_map_stage() {
+    let mut engine = StageEngine::<u32, u32>::new().add_stage(filter_map(|x: &u32| {
+        x.is_multiple_of(2).then_some(*x as u64 * 100)
+    }));
+
+    for x in 1..=6u32 {
+        engine.send(&x);
+    }
+
+    assert_eq!(engine.receive(), Some(200u64));
+    assert_eq!(engine.receive(), Some(400u64));
+    assert_eq!(engine.receive(), Some(600u64));
+}
+
+#[test]
+fn test_resequenced_pipeline_keeps_input_order() {
+    // Delays items with an odd sequence number behind the next even one
+    struct SwapPairs {
+        held: Option<Sequenced<u64>>,
+    }
+    impl Stage<Sequenced<u64>, Sequenced<u64>> for SwapPairs {
+        fn process<C>(&mut self, data: &Sequenced<u64>, collector: &mut C)
+        where
+            C: OutputCollector<Sequenced<u64>>,
+        {
+            thread::sleep(Duration::from_micros(100));
+            match self.held.take() {
+                Some(held) => {
+                    collector.push(data);
+                    collector.push(&held);
+                }
+                None => self.held = Some(*data),
+            }
+        }
+    }
+
+    let mut engine = StageEngine::<u64, u64>::new()
+        .add_stage(sequence_number::<u64>())
+        .add_stage(SwapPairs { held: None })
+        .add_stage(pipe![resequence::<u64>(16), strip_sequence::<u64>()]);
+
+    for i in 0..100 {
+        engine.send(&i);
+    }
+
+    for i in 0..100 {
+        assert_eq!(engine.receive(), Some(i));
+    }
+}
+
+assert_pipeline_types!(
+    u64,
+    sequence_number::<u64>() => Sequenced<u64>,
+    resequence::<u64>(16) => Sequenced<u64>,
+    strip_sequence::<u64>() => u64,
+    |x: &u64| Some(*x * 2) => u64,
+);
+
+#[test]
+fn test_assert_pipeline_types_in_fn() {
+    assert_pipeline_types!(u32, filter(|x: &u32| *x > 1) => u32);
+
+    let mut engine = StageEngine::<u32, u32>::new().add_stage(filter(|x: &u32| *x > 1));
+    engine.send(&1);
+    engine.send(&2);
+    assert_eq!(engine.receive(), Some(2));
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Reading {
+    id: u64,
+    value: f64,
+}
+
+#[test]
+fn test_split_at_routes_by_predicate() {
+    let (high, low) = StageEngine::<Reading, Reading>::new()
+        .add_stage(|r: &Reading| {
+            Some(Reading {
+                id: r.id,
+                value: r.value * 10.0,
+            })
+        })
+        .split_at(|r: &Reading| r.value > 50.0);
+
+    let mut high = high.add_stage(|r: &Reading| Some(r.id));
+
+    for id in 0..10 {
+        high.send(&Reading {
+            id,
+            value: id as f64,
+        });
+    }
+
+    for id in 6..10 {
+        assert_eq!(high.receive(), Some(id));
+    }
+    for id in 0..6 {
+        assert_eq!(low.receive().map(|r| r.id), Some(id));
+    }
+    assert_eq!(high.try_receive(), None);
+    assert_eq!(low.try_receive(), None);
+
+    high.send(&Reading { id: 42, value: 1.0 });
+    assert_eq!(low.receive().map(|r| r.id), Some(42));
+}
+
+#[test]
+#[should_panic(expected = "has no input")]
+fn test_split_at_second_branch_has_no_input() {
+    let (_, mut rest) = StageEngine::<u32, u32>::new().split_at(|x: &u32| *x > 1);
+    rest.send(&1);
+}
+
+#[test]
+fn test_error_handler_skips_panicking_items() {
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let handler_messages = messages.clone();
+    let mut engine = StageEngine::<u32, u32>::new()
+        .with_error_handler(move |message| handler_messages.lock().unwrap().push(message))
+        .add_stage(|x: &u32| {
+            if x.is_multiple_of(3) {
+                panic!("cannot process {}", x);
+            }
+            Some(*x * 10)
+        });
+
+    for i in 1..=7 {
+        engine.send(&i);
+    }
+
+    for expected in [10, 20, 40, 50, 70] {
+        assert_eq!(engine.receive(), Some(expected));
+    }
+    assert_eq!(engine.panic_count(), 2);
+    assert_eq!(
+        *messages.lock().unwrap(),
+        vec![
+            "cannot process 3".to_string(),
+            "cannot process 6".to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_pipeline_report_counts_items() {
+    struct Duplicate;
+    impl Stage<u32, u32> for Duplicate {
+        fn process<C>(&mut self, data: &u32, collector: &mut C)
+        where
+            C: OutputCollector<u32>,
+        {
+            collector.push(data);
+            collector.push(data);
+        }
+    }
+
+    let mut engine = StageEngine::<u32, u32>::new()
+        .with_report()
+        .add_stage(filter(|x: &u32| x.is_multiple_of(2)))
+        .add_stage(Duplicate)
+        .add_stage(|x: &u32| Some(*x as u64));
+
+    for i in 0..100 {
+        engine.send(&i);
+    }
+    assert_eq!(engine.receive_batch(100, Duration::from_secs(5)).len(), 100);
+
+    let report = engine.pipeline_report();
+    let counts: Vec<(&str, u64, u64)> = report
+        .stages
+        .iter()
+        .map(|s| (s.name.as_str(), s.items_in, s.items_out))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            ("stage_0", 100, 50),
+            ("stage_1", 50, 100),
+            ("stage_2", 100, 100)
+        ]
+    );
+    for stage in &report.stages {
+        assert!(stage.throughput_per_sec > 0.0);
+        assert!(stage.avg_latency_ns >= 0.0);
+    }
+}
+
+#[test]
+fn test_pipeline_report_is_opt_in() {
+    let mut engine = StageEngine::<u32, u32>::new()
+        .add_stage(|x: &u32| Some(*x + 1))
+        .with_report()
+        .add_stage(|x: &u32| Some(*x * 2));
+
+    engine.send(&1);
+    assert_eq!(engine.receive(), Some(4));
+
+    let report = engine.pipeline_report();
+    assert_eq!(report.stages.len(), 1);
+    assert_eq!(report.stages[0].name, "stage_1");
+}
+
+#[test]
+fn test_pipeline_report_after_split() {
+    let (mut high, low) = StageEngine::<u32, u32>::new()
+        .with_report()
+        .split_at(|x: &u32| *x >= 3);
+
+    for i in 0..10 {
+        high.send(&i);
+    }
+    assert_eq!(high.receive_batch(7, Duration::from_secs(5)).len(), 7);
+    assert_eq!(low.receive_batch(3, Duration::from_secs(5)).len(), 3);
+
+    let counts = |engine: &StageEngine<u32, u32>| {
+        let stage = &engine.pipeline_report().stages[0];
+        (stage.name.clone(), stage.items_in, stage.items_out)
+    };
+    assert_eq!(counts(&high), ("stage_0_matched".to_string(), 10, 7));
+    assert_eq!(counts(&low), ("stage_0_rest".to_string(), 10, 3));
+}
+
+#[test]
+fn test_collector_counts_pushes_per_item() {
+    // Emits `data` copies of each item, but at most two
+    struct RepeatAtMostTwice;
+    impl Stage<u32, u32> for RepeatAtMostTwice {
+        fn process<C>(&mut self, data: &u32, collector: &mut C)
+        where
+            C: OutputCollector<u32>,
+        {
+            for _ in 0..*data {
+                if collector.count() >= 2 {
+                    break;
+                }
+                collector.push(data);
+            }
+        }
+    }
+
+    // The counting wrapper of `with_report` must not hide the count
+    for engine in [StageEngine::new(), StageEngine::new().with_report()] {
+        let mut engine = engine.add_stage(RepeatAtMostTwice);
+        engine.send(&5);
+        engine.send(&1);
+
+        assert_eq!(
+            engine.receive_batch(3, Duration::from_secs(5)),
+            vec![5, 5, 1]
+        );
+        engine.await_idle(Duration::from_millis(50));
+        assert_eq!(engine.try_receive(), None);
+    }
+}
+
+#[test]
+fn test_send_batch() {
+    let mut engine =
+        StageEngine::<u32, u32>::with_capacity(20_000).add_stage(|x: &u32| Some(*x + 1));
+
+    let items: Vec<u32> = (0..10_000).collect();
+    engine.send_batch(&items[..5_000]);
+    engine.send(&items[5_000]);
+    engine.send_batch(&items[5_001..]);
+
+    let out = engine.receive_batch(10_000, Duration::from_secs(5));
+    assert_eq!(out, (1..=10_000).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_output_iter() {
+    let mut engine =
+        StageEngine::<u32, u32>::with_capacity(1_000).add_stage(|x: &u32| Some(*x * 2));
+    assert_eq!(engine.output_iter().next(), None);
+
+    for i in 0..100 {
+        engine.send(&i);
+    }
+    let out: Vec<u32> = engine
+        .blocking_output_iter(Duration::from_secs(5))
+        .take(10)
+        .collect();
+    assert_eq!(out, (0..10).map(|x| x * 2).collect::<Vec<u32>>());
+
+    engine.await_idle(Duration::from_secs(5));
+    let out: Vec<u32> = engine.output_iter().collect();
+    assert_eq!(out, (10..100).map(|x| x * 2).collect::<Vec<u32>>());
+
+    let out: Vec<u32> = engine
+        .blocking_output_iter(Duration::from_millis(10))
+        .collect();
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_drain_and_close() {
+    let mut engine =
+        StageEngine::<u32, u32>::with_capacity(1_000).add_stage(|x: &u32| Some(*x * 2));
+    assert!(engine.drain_available().is_empty());
+
+    for i in 0..100 {
+        engine.send(&i);
+    }
+    let first = engine.receive_batch(10, Duration::from_secs(5));
+    assert_eq!(first, (0..10).map(|x| x * 2).collect::<Vec<u32>>());
+
+    let rest = engine.drain_and_close(Duration::from_secs(5));
+    assert_eq!(rest, (10..100).map(|x| x * 2).collect::<Vec<u32>>());
+}
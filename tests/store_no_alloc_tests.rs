@@ -10,7 +10,7 @@ static ALLOC: AllocDisabler = AllocDisabler;
 fn test_store_push_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_push",
+        name: "no_alloc_push".into(),
         size: 1024,
         in_memory: true,
     });
@@ -24,7 +24,7 @@ fn test_store_push_no_alloc() {
 fn test_store_reader_next_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_next",
+        name: "no_alloc_next".into(),
         size: 1024,
         in_memory: true,
     });
@@ -40,7 +40,7 @@ fn test_store_reader_next_no_alloc() {
 fn test_store_reader_get_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_get",
+        name: "no_alloc_get".into(),
         size: 1024,
         in_memory: true,
     });
@@ -57,7 +57,7 @@ fn test_store_reader_get_no_alloc() {
 fn test_store_reader_get_window_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_window",
+        name: "no_alloc_window".into(),
         size: 1024,
         in_memory: true,
     });
@@ -76,7 +76,7 @@ fn test_store_reader_get_window_no_alloc() {
 fn test_store_reader_get_at_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_get_at",
+        name: "no_alloc_get_at".into(),
         size: 1024,
         in_memory: true,
     });
@@ -92,7 +92,7 @@ fn test_store_reader_get_at_no_alloc() {
 fn test_store_reader_get_last_no_alloc() {
     let engine = RodaEngine::new();
     let mut store = engine.new_journal_store::<u32>(JournalStoreOptions {
-        name: "no_alloc_get_last",
+        name: "no_alloc_get_last".into(),
         size: 1024,
         in_memory: true,
     });
@@ -0,0 +1,79 @@
+use roda_state::{JournalStoreOptions, RodaEngine, TickStore};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Tick {
+    ts: u64,
+    price: f64,
+}
+
+fn tick_store(engine: &RodaEngine, name: &'static str) -> TickStore<Tick> {
+    let store = engine.new_journal_store::<Tick>(JournalStoreOptions {
+        name: name.into(),
+        size: 1024,
+        in_memory: true,
+    });
+    TickStore::new(store, |t| t.ts)
+}
+
+fn ticks() -> Vec<Tick> {
+    // Uneven spacing with repeated timestamps
+    let mut ts = 100;
+    (0..200)
+        .map(|i| {
+            ts += (i * 7) % 5;
+            Tick {
+                ts,
+                price: i as f64,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_tick_store_get_at_time_matches_linear_scan() {
+    let engine = RodaEngine::new();
+    let mut store = tick_store(&engine, "tick_get_at_time");
+    let ticks = ticks();
+    for t in &ticks {
+        store.append(t);
+    }
+
+    let max_ts = ticks.last().unwrap().ts;
+    for ts in 0..max_ts + 10 {
+        let expected = ticks.iter().rev().find(|t| t.ts <= ts).copied();
+        assert_eq!(store.get_at_time(ts), expected, "ts {}", ts);
+    }
+}
+
+#[test]
+fn test_tick_store_range_by_time_matches_linear_scan() {
+    let engine = RodaEngine::new();
+    let mut store = tick_store(&engine, "tick_range_by_time");
+    let ticks = ticks();
+    for t in &ticks {
+        store.append(t);
+    }
+
+    let max_ts = ticks.last().unwrap().ts;
+    for start in (90..max_ts + 10).step_by(13) {
+        for end in [start, start + 1, start + 17, start + 200] {
+            let expected: Vec<Tick> = ticks
+                .iter()
+                .filter(|t| t.ts >= start && t.ts < end)
+                .copied()
+                .collect();
+            assert_eq!(store.range_by_time(start, end), expected.as_slice());
+        }
+    }
+    assert!(store.range_by_time(500, 100).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Tick out of order")]
+fn test_tick_store_rejects_out_of_order() {
+    let engine = RodaEngine::new();
+    let mut store = tick_store(&engine, "tick_out_of_order");
+    store.append(&Tick { ts: 10, price: 1.0 });
+    store.append(&Tick { ts: 5, price: 2.0 });
+}
@@ -0,0 +1,73 @@
+use roda_state::{JournalStoreOptions, RodaEngine, Waterfall};
+use std::time::Duration;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Reading {
+    sensor: u32,
+    value: u32,
+}
+
+fn options(name: &'static str) -> JournalStoreOptions {
+    JournalStoreOptions {
+        name: name.into(),
+        size: 1024,
+        in_memory: true,
+    }
+}
+
+#[test]
+fn test_waterfall_three_layers() {
+    let engine = RodaEngine::new();
+    let mut waterfall = Waterfall::<Reading>::new(&engine, options("raw"));
+
+    // Drop invalid readings
+    waterfall
+        .add_layer(options("filtered"))
+        .run_transform(|r| (r.value != 0).then_some(r));
+
+    // Running sum per item
+    let mut sum = 0;
+    waterfall
+        .add_layer(options("aggregated"))
+        .run_transform(move |r| {
+            sum += r.value;
+            Some(Reading {
+                sensor: r.sensor,
+                value: sum,
+            })
+        });
+
+    // Keep only even sensors
+    let layer = waterfall.add_layer(options("indexed"));
+    assert_eq!(layer.output_store().size(), 0);
+    layer.run_transform(|r| r.sensor.is_multiple_of(2).then_some(r));
+    assert_eq!(waterfall.layer_count(), 3);
+
+    for (sensor, value) in [(0, 5), (1, 0), (2, 3), (3, 4), (4, 0), (6, 1)] {
+        waterfall.send(&Reading { sensor, value });
+    }
+
+    engine.await_idle(Duration::from_secs(1));
+
+    let reader = waterfall.reader();
+    let mut out = Vec::new();
+    reader.handle_remaining(|r| out.push(*r));
+    assert_eq!(
+        out,
+        vec![
+            Reading {
+                sensor: 0,
+                value: 5
+            },
+            Reading {
+                sensor: 2,
+                value: 8
+            },
+            Reading {
+                sensor: 6,
+                value: 13
+            },
+        ]
+    );
+}